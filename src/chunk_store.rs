@@ -22,7 +22,7 @@ use crate::{utils, vault::Init};
 use chunk::{Chunk, ChunkId};
 use error::{Error, Result};
 use hex;
-use log::trace;
+use log::{trace, warn};
 use safe_nd::{AData, IData, LoginPacket, MData};
 use std::{
     cell::Cell,
@@ -39,6 +39,9 @@ const CHUNK_STORE_DIR: &str = "chunks";
 /// The max name length for a chunk file.
 const MAX_CHUNK_FILE_NAME_LENGTH: usize = 104;
 
+/// The extension of the file a chunk is written to before being renamed into place.
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
 pub(crate) type ImmutableChunkStore = ChunkStore<IData>;
 pub(crate) type MutableChunkStore = ChunkStore<MData>;
 pub(crate) type AppendOnlyChunkStore = ChunkStore<AData>;
@@ -76,16 +79,32 @@ where
 
         match init_mode {
             Init::New => Self::create_new_root(&dir)?,
-            Init::Load => trace!("Loading ChunkStore at {}", dir.display()),
+            Init::Load => {
+                trace!("Loading ChunkStore at {}", dir.display());
+                Self::remove_temp_files(&dir)?;
+            }
         }
 
         let used_space = UsedSpace::new(&dir, total_used_space, init_mode)?;
-        Ok(ChunkStore {
+        let chunk_store = ChunkStore {
             dir,
             max_capacity,
             used_space,
             _phantom: PhantomData,
-        })
+        };
+
+        if init_mode == Init::Load {
+            if let Some((recorded, stored)) = chunk_store.used_space_drift()? {
+                warn!(
+                    "ChunkStore at {} records {} bytes used, but its chunks occupy {} bytes.",
+                    chunk_store.dir.display(),
+                    recorded,
+                    stored
+                );
+            }
+        }
+
+        Ok(chunk_store)
     }
 }
 
@@ -95,34 +114,66 @@ impl<T: Chunk> ChunkStore<T> {
         fs::create_dir_all(root)?;
 
         // Verify that chunk files can be created.
-        let temp_file_path = root.join("0".repeat(MAX_CHUNK_FILE_NAME_LENGTH));
+        let temp_file_path = root
+            .join("0".repeat(MAX_CHUNK_FILE_NAME_LENGTH))
+            .with_extension(TEMP_FILE_EXTENSION);
         let _ = File::create(&temp_file_path)?;
         fs::remove_file(temp_file_path)?;
 
         Ok(())
     }
 
+    // Removes any chunks which were only partly written when the vault last stopped.
+    fn remove_temp_files(root: &Path) -> Result<()> {
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .map_or(false, |ext| ext == TEMP_FILE_EXTENSION)
+            {
+                trace!("Removing partly-written chunk {}", path.display());
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Stores a new data chunk.
     ///
     /// If there is not enough storage space available, returns `Error::NotEnoughSpace`.  In case of
     /// an IO error, it returns `Error::Io`.
     ///
-    /// If a chunk with the same id already exists, it will be overwritten.
+    /// If a chunk with the same id already exists, it will be overwritten.  The chunk is written to
+    /// a temporary file which is then renamed over any existing one, so a chunk file is never left
+    /// partly written, and an existing chunk is kept if writing its replacement fails.
     pub fn put(&mut self, chunk: &T) -> Result<()> {
         let serialised_chunk = utils::serialise(chunk);
         let consumed_space = serialised_chunk.len() as u64;
-        if self.used_space.total().saturating_add(consumed_space) > self.max_capacity {
+        let file_path = self.file_path(chunk.id())?;
+        let replaced_space = fs::metadata(&file_path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        if self
+            .used_space
+            .total()
+            .saturating_sub(replaced_space)
+            .saturating_add(consumed_space)
+            > self.max_capacity
+        {
             return Err(Error::NotEnoughSpace);
         }
 
-        let file_path = self.file_path(chunk.id())?;
-        let _ = self.do_delete(&file_path);
-
-        let mut file = File::create(&file_path)?;
+        let temp_file_path = file_path.with_extension(TEMP_FILE_EXTENSION);
+        let mut file = File::create(&temp_file_path)?;
         file.write_all(&serialised_chunk)?;
         file.sync_data()?;
+        fs::rename(&temp_file_path, &file_path)?;
 
-        self.used_space.increase(consumed_space)
+        if consumed_space >= replaced_space {
+            self.used_space.increase(consumed_space - replaced_space)
+        } else {
+            self.used_space.decrease(replaced_space - consumed_space)
+        }
     }
 
     /// Deletes the data chunk stored under `id`.
@@ -173,6 +224,26 @@ impl<T: Chunk> ChunkStore<T> {
             .unwrap_or_else(|_| Vec::new())
     }
 
+    /// Compares the recorded used space with the total size of the chunk files on disk, returning
+    /// both as `(recorded, stored)` if they differ.  Nothing is corrected.
+    pub fn used_space_drift(&self) -> Result<Option<(u64, u64)>> {
+        let mut stored = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && to_chunk_id::<T::Id>(entry).is_some() {
+                stored += metadata.len();
+            }
+        }
+
+        let recorded = self.used_space.local();
+        if recorded == stored {
+            Ok(None)
+        } else {
+            Ok(Some((recorded, stored)))
+        }
+    }
+
     fn do_delete(&mut self, file_path: &Path) -> Result<()> {
         if let Ok(metadata) = fs::metadata(file_path) {
             self.used_space.decrease(metadata.len())?;
@@ -9,12 +9,13 @@
 use super::{
     chunk::{Chunk, ChunkId},
     error::Error,
-    ChunkStore, Subdir,
+    used_space::UsedSpace,
+    ChunkStore, Subdir, TEMP_FILE_EXTENSION,
 };
 use crate::{vault::Init, ToDbKey};
 use rand::{distributions::Standard, rngs::ThreadRng, Rng};
 use serde::{Deserialize, Serialize};
-use std::{cell::Cell, path::Path, rc::Rc, u64};
+use std::{cell::Cell, collections::HashMap, fs, path::Path, rc::Rc, u64};
 use tempdir::TempDir;
 use unwrap::unwrap;
 
@@ -154,6 +155,151 @@ fn failed_put_when_not_enough_space() {
     }
 }
 
+#[test]
+fn put_at_capacity_boundary() {
+    let mut rng = new_rng();
+    let data = Data {
+        id: Id(rng.gen()),
+        value: rng.sample_iter(&Standard).take(100).collect(),
+    };
+    let size = unwrap!(bincode::serialized_size(&data));
+
+    for &(capacity, should_succeed) in &[(size + 1, true), (size, true), (size - 1, false)] {
+        let root = temp_dir();
+        let used_space = Rc::new(Cell::new(0));
+        let mut chunk_store = unwrap!(ChunkStore::new(
+            root.path(),
+            capacity,
+            Rc::clone(&used_space),
+            Init::New
+        ));
+
+        match chunk_store.put(&data) {
+            Ok(()) if should_succeed => {
+                assert!(chunk_store.has(&data.id));
+                assert_eq!(used_space.get(), size);
+            }
+            Err(Error::NotEnoughSpace) if !should_succeed => {
+                // Nothing should have been written.
+                assert!(!chunk_store.has(&data.id));
+                assert_eq!(used_space.get(), 0);
+            }
+            x => panic!("Unexpected result with capacity {}: {:?}", capacity, x),
+        }
+    }
+}
+
+#[test]
+fn chunks_persist_across_reload() {
+    let mut rng = new_rng();
+    let chunks = Chunks::gen(&mut rng);
+    let root = temp_dir();
+
+    {
+        let mut chunk_store = unwrap!(ChunkStore::<Data>::new(
+            root.path(),
+            u64::MAX,
+            Rc::new(Cell::new(0)),
+            Init::New
+        ));
+        for (index, (data, _)) in chunks.data_and_sizes.iter().enumerate() {
+            unwrap!(chunk_store.put(&Data {
+                id: Id(index as u64),
+                value: data.clone(),
+            }));
+        }
+    }
+
+    let used_space = Rc::new(Cell::new(0));
+    let chunk_store = unwrap!(ChunkStore::<Data>::new(
+        root.path(),
+        u64::MAX,
+        Rc::clone(&used_space),
+        Init::Load
+    ));
+    assert_eq!(used_space.get(), chunks.total_size);
+    assert_eq!(chunk_store.keys().len(), chunks.data_and_sizes.len());
+    for (index, (data, _)) in chunks.data_and_sizes.iter().enumerate() {
+        let retrieved = unwrap!(chunk_store.get(&Id(index as u64)));
+        assert_eq!(*data, retrieved.value);
+    }
+}
+
+#[test]
+fn used_space_matches_stored_chunks() {
+    let mut rng = new_rng();
+    let root = temp_dir();
+    let capacity = 2_000;
+    let used_space = Rc::new(Cell::new(0));
+    let mut chunk_store = unwrap!(ChunkStore::<Data>::new(
+        root.path(),
+        capacity,
+        Rc::clone(&used_space),
+        Init::New
+    ));
+    // Map of chunk ID to the serialised size of the chunk stored under it.
+    let mut stored = HashMap::new();
+
+    for _ in 0..500 {
+        let id = Id(rng.gen_range(0, 20));
+        if rng.gen() {
+            let size: u8 = rng.gen();
+            let data = Data {
+                id,
+                value: rng.sample_iter(&Standard).take(size as usize).collect(),
+            };
+            let serialised_size = unwrap!(bincode::serialized_size(&data));
+            let used_before = used_space.get();
+            match chunk_store.put(&data) {
+                Ok(()) => {
+                    let _ = stored.insert(id, serialised_size);
+                }
+                // A failed Put leaves any existing chunk under `id` in place.
+                Err(Error::NotEnoughSpace) => {
+                    assert!(used_before + serialised_size > capacity);
+                    assert_eq!(used_space.get(), used_before);
+                }
+                Err(error) => panic!("Unexpected error: {:?}", error),
+            }
+        } else {
+            unwrap!(chunk_store.delete(&id));
+            let _ = stored.remove(&id);
+        }
+
+        assert_eq!(used_space.get(), stored.values().sum::<u64>());
+        assert!(used_space.get() <= capacity);
+    }
+}
+
+#[test]
+fn used_space_drift_is_detected() {
+    let mut rng = new_rng();
+    let root = temp_dir();
+    let mut chunk_store = unwrap!(ChunkStore::<Data>::new(
+        root.path(),
+        u64::MAX,
+        Rc::new(Cell::new(0)),
+        Init::New
+    ));
+
+    for index in 0..3 {
+        unwrap!(chunk_store.put(&Data {
+            id: Id(index),
+            value: rng.sample_iter(&Standard).take(10).collect(),
+        }));
+    }
+    assert_eq!(unwrap!(chunk_store.used_space_drift()), None);
+
+    // Remove a chunk behind the store's back.
+    let used_space = chunk_store.used_space.local();
+    let chunk_size = unwrap!(fs::metadata(unwrap!(chunk_store.file_path(&Id(0))))).len();
+    unwrap!(fs::remove_file(unwrap!(chunk_store.file_path(&Id(0)))));
+    assert_eq!(
+        unwrap!(chunk_store.used_space_drift()),
+        Some((used_space, used_space - chunk_size))
+    );
+}
+
 #[test]
 fn delete() {
     let mut rng = new_rng();
@@ -240,6 +386,52 @@ fn overwrite_value() {
     }
 }
 
+#[test]
+fn failed_overwrite_keeps_existing_chunk() {
+    let mut rng = new_rng();
+    let root = temp_dir();
+    let used_space = Rc::new(Cell::new(0));
+    let mut chunk_store = unwrap!(ChunkStore::new(
+        root.path(),
+        u64::MAX,
+        Rc::clone(&used_space),
+        Init::New
+    ));
+
+    let id = Id(rng.gen());
+    let old_data = Data {
+        id,
+        value: rng.sample_iter(&Standard).take(10).collect(),
+    };
+    let new_data = Data {
+        id,
+        value: rng.sample_iter(&Standard).take(20).collect(),
+    };
+    let old_size = unwrap!(bincode::serialized_size(&old_data));
+    unwrap!(chunk_store.put(&old_data));
+    assert_eq!(used_space.get(), old_size);
+
+    // Block the temporary file the replacement is written to, so the write fails.
+    let temp_file_path = unwrap!(chunk_store.file_path(&id)).with_extension(TEMP_FILE_EXTENSION);
+    unwrap!(fs::create_dir(&temp_file_path));
+    match chunk_store.put(&new_data) {
+        Err(Error::Io(_)) => (),
+        x => panic!("Unexpected: {:?}", x),
+    }
+    assert_eq!(unwrap!(chunk_store.get(&id)), old_data);
+    assert_eq!(used_space.get(), old_size);
+
+    // Once the write can succeed, the replacement only adds the difference in size.
+    unwrap!(fs::remove_dir(&temp_file_path));
+    unwrap!(chunk_store.put(&new_data));
+    assert_eq!(unwrap!(chunk_store.get(&id)), new_data);
+    assert_eq!(
+        used_space.get(),
+        unwrap!(bincode::serialized_size(&new_data))
+    );
+    assert_eq!(unwrap!(chunk_store.used_space_drift()), None);
+}
+
 #[test]
 fn get_fails_when_key_does_not_exist() {
     let root = temp_dir();
@@ -296,3 +488,32 @@ fn keys() {
         assert_eq!(keys.len(), chunks.data_and_sizes.len() - index - 1);
     }
 }
+
+#[test]
+fn used_space_over_release_is_clamped() {
+    let dir_a = temp_dir();
+    let dir_b = temp_dir();
+    let total_used_space = Rc::new(Cell::new(0));
+    let mut used_space_a = unwrap!(UsedSpace::new(
+        dir_a.path(),
+        Rc::clone(&total_used_space),
+        Init::New
+    ));
+    let mut used_space_b = unwrap!(UsedSpace::new(
+        dir_b.path(),
+        Rc::clone(&total_used_space),
+        Init::New
+    ));
+
+    unwrap!(used_space_a.increase(10));
+    unwrap!(used_space_b.increase(5));
+    assert_eq!(total_used_space.get(), 15);
+
+    // Releasing more than `a` consumed must not credit the space consumed by `b`.
+    unwrap!(used_space_a.decrease(15));
+    assert_eq!(total_used_space.get(), 5);
+
+    unwrap!(used_space_a.increase(1));
+    unwrap!(used_space_b.decrease(5));
+    assert_eq!(total_used_space.get(), 1);
+}
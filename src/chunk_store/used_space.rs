@@ -9,6 +9,7 @@
 use super::error::{Error, Result};
 use crate::vault::Init;
 use bincode;
+use log::error;
 use std::{
     cell::Cell,
     fs::{File, OpenOptions},
@@ -46,7 +47,9 @@ impl UsedSpace {
             let mut buffer = vec![];
             let _ = local_record.read_to_end(&mut buffer)?;
             // TODO - if this can't be parsed, we should consider emptying `dir` of any chunks.
-            bincode::deserialize::<u64>(&buffer)?
+            let local_value = bincode::deserialize::<u64>(&buffer)?;
+            total_used_space.set(total_used_space.get().saturating_add(local_value));
+            local_value
         } else {
             bincode::serialize_into(&mut local_record, &0_u64)?;
             0
@@ -63,6 +66,11 @@ impl UsedSpace {
         self.total_value.get()
     }
 
+    /// Returns the space consumed by this one `ChunkStore`.
+    pub fn local(&self) -> u64 {
+        self.local_value
+    }
+
     pub fn increase(&mut self, consumed: u64) -> Result<()> {
         let new_total = self
             .total_value
@@ -76,9 +84,21 @@ impl UsedSpace {
         self.record_new_values(new_total, new_local)
     }
 
+    /// Releases `released` bytes.  Releasing more than this `ChunkStore` has recorded as consumed
+    /// indicates an accounting bug, so it is logged and clamped to avoid crediting the space used
+    /// by other `ChunkStore`s.
     pub fn decrease(&mut self, released: u64) -> Result<()> {
+        let released = if released > self.local_value {
+            error!(
+                "Attempted to release {} bytes while only {} are recorded as used. Clamping to {}.",
+                released, self.local_value, self.local_value
+            );
+            self.local_value
+        } else {
+            released
+        };
         let new_total = self.total_value.get().saturating_sub(released);
-        let new_local = self.local_value.saturating_sub(released);
+        let new_local = self.local_value - released;
         self.record_new_values(new_total, new_local)
     }
 
@@ -8,10 +8,16 @@
 
 mod auth_keys;
 mod balance;
+mod pricing;
+mod rate_limiter;
+#[cfg(all(test, feature = "mock"))]
+mod tests;
 
+pub use self::pricing::{FlatPricing, FreeTier, PricingPolicy, ProportionalPricing};
 use self::{
     auth_keys::AuthKeysDb,
     balance::{Balance, BalancesDb},
+    rate_limiter::RateLimiter,
 };
 use crate::{
     action::Action,
@@ -24,7 +30,7 @@ use crate::{
     Config, Error, Result,
 };
 use bytes::Bytes;
-use crossbeam_channel::{self, Receiver};
+use crossbeam_channel::{self, Receiver, Sender, TrySendError};
 use lazy_static::lazy_static;
 use log::{error, info, trace, warn};
 use safe_nd::{
@@ -33,13 +39,14 @@ use safe_nd::{
     PublicId, PublicKey, Request, Response, Result as NdResult, Signature, Transaction,
     TransactionId, XorName,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
     cell::Cell,
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::{self, Display, Formatter},
     net::SocketAddr,
     rc::Rc,
+    time::{Duration, Instant, SystemTime},
 };
 use unwrap::unwrap;
 
@@ -48,11 +55,67 @@ lazy_static! {
     pub static ref COST_OF_PUT: Coins = unwrap!(Coins::from_nano(1));
 }
 
+/// Summary of the state held by a vault's client handler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ClientHandlerStats {
+    /// The number of balances held.
+    pub balance_count: usize,
+    /// The sum of all balances held, in nano coins.
+    pub total_balance_nano: u64,
+    /// The number of paid requests awaiting a response.
+    pub pending_requests: usize,
+    /// The number of balance events dropped because the event channel was full.  A non-zero
+    /// count means the events received don't record every change to the balances.
+    pub dropped_balance_events: u64,
+}
+
+/// A change to a balance held by a vault's client handler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BalanceEvent {
+    /// When the change was made.
+    pub time: SystemTime,
+    /// The owner of the balance.
+    pub owner: PublicKey,
+    /// The kind of change made.
+    pub kind: BalanceEventKind,
+    /// The amount the balance was created with, or changed by.
+    pub amount: Coins,
+}
+
+/// The kind of a `BalanceEvent`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BalanceEventKind {
+    /// A balance was created.
+    Created,
+    /// Coins were withdrawn from a balance, e.g. to pay for a request or to transfer coins.
+    Withdrawn,
+    /// Coins were deposited into a balance, e.g. as a refund or a transfer of coins.
+    Deposited,
+}
+
+/// How `ClientHandler::import_balances` treats a balance which already exists.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImportPolicy {
+    /// Leave the existing balance unchanged.
+    Skip,
+    /// Add the imported coins to the existing balance.
+    Merge,
+}
+
 #[derive(Clone, Debug)]
 struct ClientInfo {
     public_id: PublicId,
 }
 
+// The charge paid for a request which has been forwarded, but not yet answered.
+struct PendingCharge {
+    requester: PublicId,
+    amount: Coins,
+    // The response sent to the requester if no other response arrives in time.
+    timeout_response: Response,
+    expires_at: Instant,
+}
+
 pub(crate) struct ClientHandler {
     id: NodePublicId,
     auth_keys: AuthKeysDb,
@@ -60,6 +123,23 @@ pub(crate) struct ClientHandler {
     clients: HashMap<SocketAddr, ClientInfo>,
     // Map of new client connections to the challenge value we sent them.
     client_candidates: HashMap<SocketAddr, Vec<u8>>,
+    // Map of requests which have been paid for and are awaiting a response, to their charges.
+    pending_charges: HashMap<MessageId, PendingCharge>,
+    // The IDs of the paid requests, in the order in which they expire.  Entries for requests which
+    // have since been answered are skipped when they reach the front.
+    pending_charge_expiries: VecDeque<(Instant, MessageId)>,
+    // The maximum number of entries in `pending_charges`.  Further paid requests are rejected,
+    // without being charged, until some of these complete.
+    max_pending_charges: usize,
+    pending_request_timeout: Duration,
+    paid_request_limiter: RateLimiter,
+    pricing: Box<dyn PricingPolicy>,
+    max_object_size: Option<u64>,
+    min_type_tag: Option<u64>,
+    // Whether requests to store new data are currently rejected.
+    read_only: bool,
+    balance_event_sender: Option<Sender<BalanceEvent>>,
+    dropped_balance_events: u64,
     quic_p2p: QuicP2p,
     login_packets: LoginPacketChunkStore,
 }
@@ -88,6 +168,20 @@ impl ClientHandler {
             balances,
             clients: Default::default(),
             client_candidates: Default::default(),
+            pending_charges: Default::default(),
+            pending_charge_expiries: Default::default(),
+            max_pending_charges: config.max_pending_requests() as usize,
+            pending_request_timeout: Duration::from_millis(config.pending_request_timeout_msec()),
+            paid_request_limiter: RateLimiter::new(
+                config.max_paid_requests(),
+                Duration::from_millis(config.paid_request_interval_msec()),
+            ),
+            pricing: Box::new(ProportionalPricing::default()),
+            max_object_size: config.max_object_size(),
+            min_type_tag: config.min_type_tag(),
+            read_only: false,
+            balance_event_sender: None,
+            dropped_balance_events: 0,
             quic_p2p,
             login_packets,
         };
@@ -95,6 +189,70 @@ impl ClientHandler {
         Ok((client_handler, event_receiver))
     }
 
+    /// Returns a summary of the balances and requests currently held.
+    pub fn stats(&self) -> ClientHandlerStats {
+        ClientHandlerStats {
+            balance_count: self.balances.len(),
+            total_balance_nano: self.balances.iter().fold(0, |total, (_, balance)| {
+                total.saturating_add(balance.coins.as_nano())
+            }),
+            pending_requests: self.pending_charges.len(),
+            dropped_balance_events: self.dropped_balance_events,
+        }
+    }
+
+    /// Returns an iterator over all the balances held, with their owners' keys.
+    pub fn balances<'a>(&'a self) -> impl Iterator<Item = (PublicKey, Coins)> + 'a {
+        self.balances
+            .iter()
+            .map(|(owner, balance)| (owner, balance.coins))
+    }
+
+    /// Sets the policy used to price Puts of data.
+    pub fn set_pricing_policy(&mut self, pricing: Box<dyn PricingPolicy>) {
+        self.pricing = pricing;
+    }
+
+    /// Sets whether requests to store new data are rejected.  Other requests are unaffected.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Sets the channel to which an event is sent for every change to a balance.  Sending never
+    /// blocks: if `sender` is bounded and full, the event is dropped and counted in `stats()`.
+    pub fn set_balance_event_sender(&mut self, sender: Sender<BalanceEvent>) {
+        self.balance_event_sender = Some(sender);
+    }
+
+    /// Creates the given balances, handling any which already exist according to `policy`.
+    /// Balances which can't be imported are logged and skipped.  Returns the number of balances
+    /// created or changed.
+    pub fn import_balances<I>(&mut self, balances: I, policy: ImportPolicy) -> usize
+    where
+        I: IntoIterator<Item = (PublicKey, Coins)>,
+    {
+        let mut imported = 0;
+        for (owner, amount) in balances {
+            let result = if !self.balances.exists(&owner) {
+                let result = self.put_balance(&owner, &Balance { coins: amount });
+                if result.is_ok() {
+                    self.send_balance_event(BalanceEventKind::Created, owner, amount);
+                }
+                result
+            } else if policy == ImportPolicy::Merge {
+                self.deposit(&owner, amount)
+            } else {
+                trace!("{}: Not importing existing balance of {}", self, owner);
+                continue;
+            };
+            match result {
+                Ok(()) => imported += 1,
+                Err(error) => warn!("{}: Failed to import balance of {}: {}", self, owner, error),
+            }
+        }
+        imported
+    }
+
     fn setup_quic_p2p(config: &QuicP2pConfig) -> Result<(QuicP2p, Receiver<Event>)> {
         let (event_sender, event_receiver) = crossbeam_channel::unbounded();
         let mut quic_p2p = quic_p2p::Builder::new(event_sender)
@@ -233,10 +391,10 @@ impl ClientHandler {
     ) -> Option<Action> {
         use Request::*;
         trace!(
-            "{}: Received ({:?} {:?}) from {}",
+            "{}: {}: Received {:?} from {}",
             self,
+            utils::LogId(message_id),
             request,
-            message_id,
             client.public_id
         );
 
@@ -244,6 +402,18 @@ impl ClientHandler {
         self.authorise_app(&client.public_id, &request, message_id)?;
         self.verify_consistent_address(&client.public_id, &request, message_id)?;
 
+        if self.read_only && stores_new_data(&request) {
+            trace!(
+                "{}: {}: Rejecting request from {}: read-only mode is enabled",
+                self,
+                utils::LogId(message_id),
+                client.public_id
+            );
+            let response = request.error_response(NdError::from("Vault is read-only"));
+            self.send_response_to_client(&client.public_id, message_id, response);
+            return None;
+        }
+
         match request {
             //
             // ===== Immutable Data =====
@@ -396,8 +566,12 @@ impl ClientHandler {
             Ok(_) => true,
             Err(error) => {
                 warn!(
-                    "{}: ({:?}/{:?}) from {} is invalid: {}",
-                    self, request, message_id, client_id, error
+                    "{}: {}: {:?} from {} is invalid: {}",
+                    self,
+                    utils::LogId(*message_id),
+                    request,
+                    client_id,
+                    error
                 );
                 false
             }
@@ -476,13 +650,15 @@ impl ClientHandler {
             return None;
         }
 
+        self.check_type_tag(&client.public_id, chunk.tag(), message_id)?;
+        let cost = self.price_put(&client.public_id, &chunk, message_id)?;
         let request = Request::PutMData(chunk);
         self.pay(
             &client.public_id,
             owner.public_key(),
             &request,
             message_id,
-            *COST_OF_PUT,
+            cost,
         )?;
 
         Some(Action::ForwardClientRequest(Rpc::Request {
@@ -519,13 +695,14 @@ impl ClientHandler {
             }
         }
 
+        let cost = self.price_put(&client.public_id, &chunk, message_id)?;
         let request = Request::PutIData(chunk);
         self.pay(
             &client.public_id,
             owner.public_key(),
             &request,
             message_id,
-            *COST_OF_PUT,
+            cost,
         )?;
 
         Some(Action::ForwardClientRequest(Rpc::Request {
@@ -605,13 +782,15 @@ impl ClientHandler {
             return None;
         }
 
+        self.check_type_tag(&client.public_id, chunk.tag(), message_id)?;
+        let cost = self.price_put(&client.public_id, &chunk, message_id)?;
         let request = Request::PutAData(chunk);
         self.pay(
             &client.public_id,
             owner.public_key(),
             &request,
             message_id,
-            *COST_OF_PUT,
+            cost,
         )?;
 
         Some(Action::ForwardClientRequest(Rpc::Request {
@@ -743,10 +922,10 @@ impl ClientHandler {
     ) -> Option<Action> {
         use Request::*;
         trace!(
-            "{}: Received ({:?} {:?}) from src {} (client {:?})",
+            "{}: {}: Received {:?} from src {} (client {:?})",
             self,
+            utils::LogId(message_id),
             request,
-            message_id,
             src,
             requester
         );
@@ -850,19 +1029,33 @@ impl ClientHandler {
     ) -> Option<Action> {
         use Response::*;
         trace!(
-            "{}: Received ({:?} {:?}) to {} from {}",
+            "{}: {}: Received {:?} to {} from {}",
             self,
+            utils::LogId(message_id),
             response,
-            message_id,
             requester,
             data_handlers
         );
 
-        if let Some(refund_amount) = refund {
+        let charge = self
+            .pending_charges
+            .remove(&message_id)
+            .map(|pending| pending.amount);
+        // A failed mutation is refunded exactly what was charged for it (which may be nothing).
+        // Other refunds (e.g. of a transaction's amount) are taken as given by the data handlers.
+        let refund = match response {
+            Mutation(_) => refund.and(charge),
+            _ => refund,
+        };
+        if let Some(refund_amount) = refund.filter(|amount| amount.as_nano() > 0) {
             if let Err(error) = self.deposit(requester.name(), refund_amount) {
                 error!(
-                    "{}: Failed to refund {} coins for {:?}: {:?}",
-                    self, refund_amount, requester, error,
+                    "{}: {}: Failed to refund {} coins for {:?}: {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    refund_amount,
+                    requester,
+                    error,
                 )
             };
         }
@@ -927,10 +1120,7 @@ impl ClientHandler {
         });
 
         // For phase 1 we allow owners to create their own balance freely.
-        let own_request = utils::own_key(requester)
-            .map(|key| key == &owner_key)
-            .unwrap_or(false);
-        if own_request {
+        if is_own_key(requester, &owner_key) {
             return Some(action);
         }
 
@@ -952,6 +1142,8 @@ impl ClientHandler {
             Err(error) => Err(error),
         };
 
+        // The request won't be forwarded, so no response will settle what was paid for it.
+        self.cancel_pending_charge(requester, message_id);
         self.send_response_to_client(requester, message_id, Response::Transaction(result));
         None
     }
@@ -1065,10 +1257,7 @@ impl ClientHandler {
         owner_key: PublicKey,
         amount: Coins,
     ) -> Result<(), NdError> {
-        let own_request = utils::own_key(requester)
-            .map(|key| key == &owner_key)
-            .unwrap_or(false);
-        if !own_request && self.balances.exists(&owner_key) {
+        if !is_own_key(requester, &owner_key) && self.balances.exists(&owner_key) {
             info!(
                 "{}: Failed to create balance for {:?}: already exists.",
                 self, owner_key
@@ -1078,6 +1267,7 @@ impl ClientHandler {
         } else {
             let balance = Balance { coins: amount };
             self.put_balance(&owner_key, &balance)?;
+            self.send_balance_event(BalanceEventKind::Created, owner_key, amount);
             Ok(())
         }
     }
@@ -1163,7 +1353,9 @@ impl ClientHandler {
             .coins
             .checked_sub(amount)
             .ok_or(NdError::InsufficientBalance)?;
-        self.put_balance(&public_key, &balance)
+        self.put_balance(&public_key, &balance)?;
+        self.send_balance_event(BalanceEventKind::Withdrawn, public_key, amount);
+        Ok(())
     }
 
     fn deposit<K: balance::Key>(&mut self, key: &K, amount: Coins) -> Result<(), NdError> {
@@ -1176,7 +1368,9 @@ impl ClientHandler {
             .checked_add(amount)
             .ok_or(NdError::ExcessiveValue)?;
 
-        self.put_balance(&public_key, &balance)
+        self.put_balance(&public_key, &balance)?;
+        self.send_balance_event(BalanceEventKind::Deposited, public_key, amount);
+        Ok(())
     }
 
     fn put_balance(&mut self, public_key: &PublicKey, balance: &Balance) -> Result<(), NdError> {
@@ -1196,6 +1390,211 @@ impl ClientHandler {
         })
     }
 
+    fn send_balance_event(&mut self, kind: BalanceEventKind, owner: PublicKey, amount: Coins) {
+        let event = BalanceEvent {
+            time: SystemTime::now(),
+            owner,
+            kind,
+            amount,
+        };
+        let result = match self.balance_event_sender {
+            Some(ref sender) => sender.try_send(event),
+            None => return,
+        };
+        match result {
+            Ok(()) => (),
+            Err(TrySendError::Full(event)) => {
+                self.dropped_balance_events += 1;
+                warn!("{}: Balance event channel full.  Dropped {:?}", self, event);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                info!("{}: Balance event receiver disconnected.", self);
+                self.balance_event_sender = None;
+            }
+        }
+    }
+
+    // Responds to the client with an error if `type_tag` is reserved, so it can't be used for new
+    // data.
+    fn check_type_tag(
+        &mut self,
+        requester_id: &PublicId,
+        type_tag: u64,
+        message_id: MessageId,
+    ) -> Option<()> {
+        match self.min_type_tag {
+            Some(min_type_tag) if type_tag < min_type_tag => {
+                trace!(
+                    "{}: {} attempted to Put data with reserved type tag {}.",
+                    self,
+                    requester_id,
+                    type_tag
+                );
+                self.send_response_to_client(
+                    requester_id,
+                    message_id,
+                    Response::Mutation(Err(NdError::from("Forbidden type tag"))),
+                );
+                None
+            }
+            _ => Some(()),
+        }
+    }
+
+    // Returns the cost of a Put of `data` under our pricing policy, or responds to the client with
+    // an error if `data` is too large to be Put or its size can't be determined.
+    fn price_put<T: Serialize>(
+        &mut self,
+        requester_id: &PublicId,
+        data: &T,
+        message_id: MessageId,
+    ) -> Option<Coins> {
+        let data_size = match utils::serialised_size(data) {
+            Ok(data_size) => data_size,
+            Err(error) => {
+                warn!(
+                    "{}: {}: Failed to determine the size of data Put by {}: {}",
+                    self,
+                    utils::LogId(message_id),
+                    requester_id,
+                    error
+                );
+                self.send_response_to_client(
+                    requester_id,
+                    message_id,
+                    Response::Mutation(Err(NdError::from(
+                        "Invalid data: size can't be determined",
+                    ))),
+                );
+                return None;
+            }
+        };
+        match self.max_object_size {
+            Some(max_object_size) if data_size > max_object_size => {
+                trace!(
+                    "{}: {}: {} attempted to Put {} bytes, exceeding the limit of {}.",
+                    self,
+                    utils::LogId(message_id),
+                    requester_id,
+                    data_size,
+                    max_object_size
+                );
+                self.send_response_to_client(
+                    requester_id,
+                    message_id,
+                    Response::Mutation(Err(NdError::ExceededSize)),
+                );
+                None
+            }
+            _ => Some(self.pricing.cost(data_size)),
+        }
+    }
+
+    /// Fails a forwarded client request for which no handler could be found.  The client is sent an
+    /// error response, and refunded what it paid for the request.
+    ///
+    /// While this vault is the only elder it handles every request itself, so nothing calls this
+    /// until requests are routed to other nodes.
+    #[cfg_attr(not(all(test, feature = "mock")), allow(unused))]
+    pub fn handle_unroutable_request(&mut self, rpc: Rpc) {
+        let (request, requester, message_id) = match rpc {
+            Rpc::Request {
+                request,
+                requester,
+                message_id,
+            } => (request, requester, message_id),
+            Rpc::Response { .. } => return,
+        };
+        trace!(
+            "{}: {}: Unable to route {:?} from {}",
+            self,
+            utils::LogId(message_id),
+            request,
+            requester
+        );
+
+        self.cancel_pending_charge(&requester, message_id);
+        // Coins moved by the request itself were withdrawn separately from any charge for it.
+        let withdrawn = match request {
+            Request::TransferCoins { amount, .. } => Some(amount),
+            Request::CreateBalance {
+                new_balance_owner,
+                amount,
+                ..
+            } if !is_own_key(&requester, &new_balance_owner) => Some(amount),
+            // The amount already funds the new balance, so only the fee for storing the login
+            // packet is returned.
+            Request::CreateLoginPacketFor { .. } => Some(*COST_OF_PUT),
+            _ => None,
+        };
+        if let Some(amount) = withdrawn.filter(|amount| amount.as_nano() > 0) {
+            if let Err(error) = self.deposit(requester.name(), amount) {
+                error!(
+                    "{}: {}: Failed to refund {} coins for {:?}: {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    amount,
+                    requester,
+                    error,
+                )
+            }
+        }
+
+        let response = request.error_response(NdError::from("Unable to route request"));
+        self.send_response_to_client(&requester, message_id, response);
+    }
+
+    // Refunds the charge paid for a request which won't be forwarded after all.
+    fn cancel_pending_charge(&mut self, requester: &PublicId, message_id: MessageId) {
+        if let Some(pending) = self.pending_charges.remove(&message_id) {
+            self.refund_charge(requester, message_id, pending.amount);
+        }
+    }
+
+    // Fails the paid requests which have awaited a response until `now` or longer, refunding
+    // their charges.  A response which arrives later is still passed on, but isn't refunded.
+    fn expire_pending_charges(&mut self, now: Instant) {
+        while let Some(&(expires_at, message_id)) = self.pending_charge_expiries.front() {
+            if expires_at > now {
+                break;
+            }
+            let _ = self.pending_charge_expiries.pop_front();
+            // The request may have been answered, and its ID reused since.
+            let is_expired = self
+                .pending_charges
+                .get(&message_id)
+                .map_or(false, |pending| pending.expires_at == expires_at);
+            if !is_expired {
+                continue;
+            }
+            let pending = unwrap!(self.pending_charges.remove(&message_id));
+            warn!(
+                "{}: {}: Request from {} timed out awaiting a response.",
+                self,
+                utils::LogId(message_id),
+                pending.requester
+            );
+            self.refund_charge(&pending.requester, message_id, pending.amount);
+            self.send_response_to_client(&pending.requester, message_id, pending.timeout_response);
+        }
+    }
+
+    fn refund_charge(&mut self, requester: &PublicId, message_id: MessageId, charge: Coins) {
+        if charge.as_nano() == 0 {
+            return;
+        }
+        if let Err(error) = self.deposit(requester.name(), charge) {
+            error!(
+                "{}: {}: Failed to refund {} coins for {:?}: {:?}",
+                self,
+                utils::LogId(message_id),
+                charge,
+                requester,
+                error,
+            )
+        }
+    }
+
     // Pays cost of a request.
     fn pay(
         &mut self,
@@ -1205,11 +1604,92 @@ impl ClientHandler {
         message_id: MessageId,
         cost: Coins,
     ) -> Option<()> {
-        trace!("{}: {} is paying {} coins", self, requester_id, cost);
-        match self.withdraw(requester_key, cost) {
-            Ok(()) => Some(()),
+        let now = Instant::now();
+        self.expire_pending_charges(now);
+
+        // Reject a request reusing the ID of one still in progress.  Charging it would overwrite
+        // the record of the first one's charge, and the data handlers would fail it anyway.
+        if self.pending_charges.contains_key(&message_id) {
+            trace!(
+                "{}: {}: {} reused the ID of a request in progress",
+                self,
+                utils::LogId(message_id),
+                requester_id
+            );
+            self.send_response_to_client(
+                requester_id,
+                message_id,
+                request.error_response(NdError::DuplicateMessageId),
+            );
+            return None;
+        }
+
+        if self.pending_charges.len() >= self.max_pending_charges {
+            warn!(
+                "{}: {}: Rejecting request from {}: {} paid requests are already pending.",
+                self,
+                utils::LogId(message_id),
+                requester_id,
+                self.pending_charges.len()
+            );
+            let error = NdError::from("Network busy: too many pending requests");
+            self.send_response_to_client(requester_id, message_id, request.error_response(error));
+            return None;
+        }
+
+        let client_name = XorName::from(*requester_key);
+        if let Err(retry_after) = self.paid_request_limiter.try_acquire(client_name, now) {
+            trace!(
+                "{}: {}: Rate limiting {}",
+                self,
+                utils::LogId(message_id),
+                requester_id
+            );
+            let error = rate_limiter::rate_limited_error(retry_after);
+            self.send_response_to_client(requester_id, message_id, request.error_response(error));
+            return None;
+        }
+
+        trace!(
+            "{}: {}: {} is paying {} coins",
+            self,
+            utils::LogId(message_id),
+            requester_id,
+            cost
+        );
+        // Free requests still need the client to hold a balance.
+        let result = if cost.as_nano() == 0 {
+            if self.balances.exists(requester_key) {
+                Ok(())
+            } else {
+                Err(NdError::NoSuchBalance)
+            }
+        } else {
+            self.withdraw(requester_key, cost)
+        };
+        match result {
+            Ok(()) => {
+                let expires_at = now + self.pending_request_timeout;
+                let pending = PendingCharge {
+                    requester: requester_id.clone(),
+                    amount: cost,
+                    timeout_response: request
+                        .error_response(NdError::from("Timed out awaiting a response")),
+                    expires_at,
+                };
+                let _ = self.pending_charges.insert(message_id, pending);
+                self.pending_charge_expiries
+                    .push_back((expires_at, message_id));
+                Some(())
+            }
             Err(error) => {
-                trace!("{}: Unable to withdraw {} coins: {}", self, cost, error);
+                trace!(
+                    "{}: {}: Unable to withdraw {} coins: {}",
+                    self,
+                    utils::LogId(message_id),
+                    cost,
+                    error
+                );
                 self.send_response_to_client(
                     requester_id,
                     message_id,
@@ -1518,8 +1998,11 @@ impl ClientHandler {
             self.is_valid_client_signature(public_id, request, &message_id, &signature)
         } else {
             warn!(
-                "{}: ({:?}/{:?}) from {} is unsigned",
-                self, request, message_id, public_id
+                "{}: {}: {:?} from {} is unsigned",
+                self,
+                utils::LogId(message_id),
+                request,
+                public_id
             );
             false
         };
@@ -1622,3 +2105,22 @@ impl Display for ClientHandler {
         write!(formatter, "{}", self.id.name())
     }
 }
+
+// Returns whether `key` is the requester's own public key.
+fn is_own_key(requester: &PublicId, key: &PublicKey) -> bool {
+    utils::own_key(requester)
+        .map(|own_key| own_key == key)
+        .unwrap_or(false)
+}
+
+// Returns whether `request` stores a new chunk, and so is rejected in read-only mode.
+fn stores_new_data(request: &Request) -> bool {
+    match request {
+        Request::PutIData(_)
+        | Request::PutMData(_)
+        | Request::PutAData(_)
+        | Request::CreateLoginPacket(_)
+        | Request::CreateLoginPacketFor { .. } => true,
+        _ => false,
+    }
+}
@@ -63,6 +63,20 @@ impl BalancesDb {
             .map(|balance| (*public_key, balance))
     }
 
+    /// Returns the number of balances held.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns an iterator over all the balances held, with their owners' keys.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (PublicKey, Balance)> + 'a {
+        self.index.values().filter_map(move |public_key| {
+            self.db
+                .get::<Balance>(&public_key.to_db_key())
+                .map(|balance| (*public_key, balance))
+        })
+    }
+
     pub fn put(&mut self, public_key: &PublicKey, balance: &Balance) -> Result<()> {
         let db_key = public_key.to_db_key();
         self.db.set(&db_key, &balance)?;
@@ -0,0 +1,115 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::COST_OF_PUT;
+use safe_nd::Coins;
+use std::cmp;
+use unwrap::unwrap;
+
+// The default size in bytes of each unit of data charged `COST_OF_PUT` by `ProportionalPricing`.
+const DEFAULT_UNIT_SIZE: u64 = 1024 * 1024;
+
+/// Determines how much a client is charged to Put data.
+pub trait PricingPolicy {
+    /// Returns the cost of a Put of data whose serialised size is `data_size` bytes.
+    fn cost(&self, data_size: u64) -> Coins;
+}
+
+/// Charges `COST_OF_PUT` for every Put, whatever the size of the data.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlatPricing;
+
+impl PricingPolicy for FlatPricing {
+    fn cost(&self, _data_size: u64) -> Coins {
+        *COST_OF_PUT
+    }
+}
+
+/// Charges `COST_OF_PUT` for every started unit of the data's size.  By default a unit is 1 MiB.
+#[derive(Clone, Copy, Debug)]
+pub struct ProportionalPricing {
+    unit_size: u64,
+}
+
+impl ProportionalPricing {
+    /// Returns a policy charging `COST_OF_PUT` per `unit_size` bytes.  `unit_size` must not be 0.
+    pub fn new(unit_size: u64) -> Self {
+        assert!(unit_size > 0, "Pricing unit size must not be 0.");
+        Self { unit_size }
+    }
+}
+
+impl Default for ProportionalPricing {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNIT_SIZE)
+    }
+}
+
+impl PricingPolicy for ProportionalPricing {
+    fn cost(&self, data_size: u64) -> Coins {
+        let started_units = data_size / self.unit_size + u64::from(data_size % self.unit_size != 0);
+        let units = cmp::max(1, started_units);
+        unwrap!(Coins::from_nano(COST_OF_PUT.as_nano() * units))
+    }
+}
+
+/// Makes Puts of data smaller than a given size free, and prices others using another policy.
+///
+/// Free Puts still require the client to hold a balance.  Balances have no storage allowance, so
+/// free data isn't accounted for anywhere else.
+#[derive(Clone, Copy, Debug)]
+pub struct FreeTier<P> {
+    free_below: u64,
+    pricing: P,
+}
+
+impl<P: PricingPolicy> FreeTier<P> {
+    /// Returns a policy under which data smaller than `free_below` bytes is free to Put, and
+    /// other data is priced by `pricing`.
+    pub fn new(free_below: u64, pricing: P) -> Self {
+        Self {
+            free_below,
+            pricing,
+        }
+    }
+}
+
+impl<P: PricingPolicy> PricingPolicy for FreeTier<P> {
+    fn cost(&self, data_size: u64) -> Coins {
+        if data_size < self.free_below {
+            unwrap!(Coins::from_nano(0))
+        } else {
+            self.pricing.cost(data_size)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proportional_pricing() {
+        let pricing = ProportionalPricing::new(10);
+        let cost_of = |data_size| pricing.cost(data_size).as_nano() / COST_OF_PUT.as_nano();
+        assert_eq!(cost_of(0), 1);
+        assert_eq!(cost_of(1), 1);
+        assert_eq!(cost_of(10), 1);
+        assert_eq!(cost_of(11), 2);
+        assert_eq!(cost_of(20), 2);
+        assert_eq!(cost_of(21), 3);
+    }
+
+    #[test]
+    fn free_tier() {
+        let pricing = FreeTier::new(10, FlatPricing);
+        assert_eq!(pricing.cost(0).as_nano(), 0);
+        assert_eq!(pricing.cost(9).as_nano(), 0);
+        assert_eq!(pricing.cost(10), *COST_OF_PUT);
+    }
+}
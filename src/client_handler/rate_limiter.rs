@@ -0,0 +1,133 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safe_nd::{Error as NdError, XorName};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Limits the number of requests each client can make within a fixed interval.
+pub(super) struct RateLimiter {
+    max_requests: u64,
+    interval: Duration,
+    // Map of client name to the start of its current window and the number of requests made in it.
+    windows: HashMap<XorName, (Instant, u64)>,
+    last_sweep: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u64, interval: Duration) -> Self {
+        Self {
+            max_requests,
+            interval,
+            windows: Default::default(),
+            last_sweep: Instant::now(),
+        }
+    }
+
+    /// Records a request from `client` made at `now`.  If the client has already used up its
+    /// allowance for the current window, the request isn't recorded and the time remaining until
+    /// the window ends is returned.
+    pub fn try_acquire(&mut self, client: XorName, now: Instant) -> Result<(), Duration> {
+        self.sweep(now);
+
+        let interval = self.interval;
+        let (start, count) = self.windows.entry(client).or_insert((now, 0));
+        let elapsed = now.duration_since(*start);
+        if elapsed >= interval {
+            *start = now;
+            *count = 0;
+        } else if *count >= self.max_requests {
+            return Err(interval - elapsed);
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    // Drops the windows which have ended, at most once per interval.
+    fn sweep(&mut self, now: Instant) {
+        if now.duration_since(self.last_sweep) < self.interval {
+            return;
+        }
+        let interval = self.interval;
+        self.windows
+            .retain(|_, (start, _)| now.duration_since(*start) < interval);
+        self.last_sweep = now;
+    }
+}
+
+/// Returns the error sent to a client which has been rate limited.  `safe_nd` has no dedicated
+/// variant, so the time to wait before retrying is given in the error's message.
+pub(super) fn rate_limited_error(retry_after: Duration) -> NdError {
+    NdError::from(format!(
+        "Rate limited: retry after {} ms",
+        retry_after.as_millis()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+    use rand::Rng;
+    use unwrap::unwrap;
+
+    #[test]
+    fn requests_beyond_allowance_are_rejected() {
+        let mut rng = rand::thread_rng();
+        let client_a: XorName = rng.gen();
+        let client_b: XorName = rng.gen();
+        let interval = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(3, interval);
+
+        for _ in 0..3 {
+            assert_eq!(limiter.try_acquire(client_a, start), Ok(()));
+        }
+        let later = start + Duration::from_secs(4);
+        assert_eq!(
+            limiter.try_acquire(client_a, later),
+            Err(Duration::from_secs(6))
+        );
+
+        // Other clients have their own allowance.
+        assert_eq!(limiter.try_acquire(client_b, later), Ok(()));
+
+        // The allowance is restored once the window ends.
+        assert_eq!(limiter.try_acquire(client_a, start + interval), Ok(()));
+    }
+
+    #[test]
+    fn ended_windows_are_dropped() {
+        let client: XorName = rand::thread_rng().gen();
+        let interval = Duration::from_secs(10);
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(3, interval);
+
+        assert_eq!(limiter.try_acquire(client, start), Ok(()));
+        assert_eq!(limiter.windows.len(), 1);
+
+        let other_client: XorName = rand::thread_rng().gen();
+        assert_eq!(
+            limiter.try_acquire(other_client, start + interval * 2),
+            Ok(())
+        );
+        assert_eq!(limiter.windows.len(), 1);
+        assert!(limiter.windows.contains_key(&other_client));
+    }
+
+    #[test]
+    fn retry_hint_survives_serialisation() {
+        let error = rate_limited_error(Duration::from_millis(1500));
+        let serialised = utils::serialise(&error);
+        let deserialised: NdError = unwrap!(bincode::deserialize(&serialised));
+        assert_eq!(deserialised, error);
+        assert!(deserialised.to_string().contains("retry after 1500 ms"));
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{BalanceEventKind, ClientHandler, ClientInfo, COST_OF_PUT};
+use crate::{action::Action, mock::Network, quic_p2p::Event, rpc::Rpc, vault::Init, Config};
+use crossbeam_channel::Receiver;
+use safe_nd::{
+    ClientFullId, Coins, Error as NdError, IData, MessageId, NodeFullId, PublicId, PublicKey,
+    Request, Response, UnpubImmutableData, XorName,
+};
+use serde::{ser::Error as SerError, Serialize, Serializer};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use tempdir::TempDir;
+use unwrap::unwrap;
+
+struct Setup {
+    handler: ClientHandler,
+    client: ClientInfo,
+    client_id: ClientFullId,
+    owner: PublicKey,
+    _event_receiver: Receiver<Event>,
+    _network: Network,
+    _root_dir: TempDir,
+}
+
+impl Setup {
+    // Returns a client handler built from `config`, and a client holding a balance of `balance`
+    // nano coins.
+    fn new(mut config: Config, balance: u64) -> Self {
+        let network = Network::new(rand::thread_rng());
+        let root_dir = unwrap!(TempDir::new("client_handler"));
+        config.set_root_dir(root_dir.path());
+
+        let id = NodeFullId::new(&mut rand::thread_rng());
+        let (mut handler, event_receiver) = unwrap!(ClientHandler::new(
+            id.public_id().clone(),
+            &config,
+            &Rc::new(Cell::new(0)),
+            Init::New
+        ));
+
+        let client_id = ClientFullId::new_ed25519(&mut rand::thread_rng());
+        let public_id = PublicId::Client(client_id.public_id().clone());
+        let owner = *client_id.public_id().public_key();
+        unwrap!(handler.create_balance(&public_id, owner, coins(balance)));
+
+        Self {
+            handler,
+            client: ClientInfo { public_id },
+            client_id,
+            owner,
+            _event_receiver: event_receiver,
+            _network: network,
+            _root_dir: root_dir,
+        }
+    }
+
+    fn new_idata(&self, value: u8) -> IData {
+        IData::Unpub(UnpubImmutableData::new(vec![value], self.owner))
+    }
+
+    fn balance(&self) -> Option<Coins> {
+        self.handler.balance(&self.owner)
+    }
+
+    // Passes `request` to the handler as though it was signed and sent by the client.
+    fn send_request(&mut self, request: Request, message_id: MessageId) -> Option<Action> {
+        let signature = self
+            .client_id
+            .sign(unwrap!(bincode::serialize(&(&request, message_id))));
+        let client = self.client.clone();
+        self.handler
+            .handle_client_request(&client, request, message_id, Some(signature))
+    }
+}
+
+fn coins(nano: u64) -> Coins {
+    unwrap!(Coins::from_nano(nano))
+}
+
+// Data whose serialised size can't be computed.  No `safe_nd` data type fails to serialise, so such
+// data can't be sent in a client request.
+struct Unserialisable;
+
+impl Serialize for Unserialisable {
+    fn serialize<S: Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(S::Error::custom("unserialisable"))
+    }
+}
+
+// Returns the RPC of the client request forwarded by `action`.
+fn forwarded_rpc(action: Option<Action>) -> Rpc {
+    match action {
+        Some(Action::ForwardClientRequest(rpc)) => rpc,
+        _ => panic!("Request should have been forwarded"),
+    }
+}
+
+#[test]
+fn paid_requests_beyond_pending_limit_are_rejected() {
+    let mut config = Config::default();
+    config.set_max_pending_requests(2);
+    let mut setup = Setup::new(config, 10);
+    let client = setup.client.clone();
+
+    // Two Puts are charged and forwarded, but not yet answered.
+    for value in 0..2 {
+        let data = setup.new_idata(value);
+        let action = setup
+            .handler
+            .handle_put_idata(&client, data, MessageId::new());
+        let _ = forwarded_rpc(action);
+    }
+    assert_eq!(setup.handler.stats().pending_requests, 2);
+    assert_eq!(setup.balance(), Some(coins(8)));
+
+    // A third is rejected without being charged.
+    let data = setup.new_idata(2);
+    assert!(setup
+        .handler
+        .handle_put_idata(&client, data, MessageId::new())
+        .is_none());
+    assert_eq!(setup.handler.stats().pending_requests, 2);
+    assert_eq!(setup.balance(), Some(coins(8)));
+}
+
+#[test]
+fn paid_request_reusing_pending_message_id_is_rejected() {
+    let mut setup = Setup::new(Config::default(), 10);
+    let message_id = MessageId::new();
+
+    // The first Put is charged and forwarded.
+    let request = Request::PutIData(setup.new_idata(0));
+    let _ = forwarded_rpc(setup.send_request(request, message_id));
+    assert_eq!(setup.balance(), Some(coins(9)));
+
+    // A second Put with the same ID while the first is in progress is neither charged nor
+    // forwarded, and the first one's charge is left in place.
+    let request = Request::PutIData(setup.new_idata(1));
+    assert!(setup.send_request(request, message_id).is_none());
+    assert_eq!(setup.balance(), Some(coins(9)));
+    assert_eq!(setup.handler.stats().pending_requests, 1);
+
+    // When the first Put fails, it's refunded exactly what it was charged, and nothing is left
+    // pending.
+    let response = Rpc::Response {
+        response: Response::Mutation(Err(NdError::from("Put failed"))),
+        requester: setup.client.public_id.clone(),
+        message_id,
+        refund: Some(*COST_OF_PUT),
+    };
+    let _ = setup.handler.handle_vault_rpc(rand::random(), response);
+    assert_eq!(setup.balance(), Some(coins(10)));
+    assert_eq!(setup.handler.stats().pending_requests, 0);
+}
+
+#[test]
+fn unroutable_requests_are_refunded_what_was_paid() {
+    let mut setup = Setup::new(Config::default(), 10);
+    let client = setup.client.clone();
+    let requester = client.public_id.clone();
+    let other_owner = *ClientFullId::new_ed25519(&mut rand::thread_rng())
+        .public_id()
+        .public_key();
+
+    // A Put is refunded its charge.
+    let data = setup.new_idata(0);
+    let action = setup
+        .handler
+        .handle_put_idata(&client, data, MessageId::new());
+    assert_eq!(setup.balance(), Some(coins(9)));
+    setup
+        .handler
+        .handle_unroutable_request(forwarded_rpc(action));
+    assert_eq!(setup.balance(), Some(coins(10)));
+    assert!(setup.handler.pending_charges.is_empty());
+
+    // Creating a balance for someone else is refunded both its fee and its amount.
+    let action = setup.handler.handle_create_balance_client_req(
+        &requester,
+        other_owner,
+        coins(3),
+        0,
+        MessageId::new(),
+    );
+    assert_eq!(setup.balance(), Some(coins(6)));
+    setup
+        .handler
+        .handle_unroutable_request(forwarded_rpc(action));
+    assert_eq!(setup.balance(), Some(coins(10)));
+
+    // Creating our own balance is free, so nothing is refunded.
+    let action = setup.handler.handle_create_balance_client_req(
+        &requester,
+        setup.owner,
+        coins(5),
+        0,
+        MessageId::new(),
+    );
+    setup
+        .handler
+        .handle_unroutable_request(forwarded_rpc(action));
+    assert_eq!(setup.balance(), Some(coins(10)));
+
+    // A transfer is refunded its amount.
+    let action = setup.handler.handle_transfer_coins_client_req(
+        &requester,
+        XorName::from(other_owner),
+        coins(4),
+        0,
+        MessageId::new(),
+    );
+    assert_eq!(setup.balance(), Some(coins(6)));
+    setup
+        .handler
+        .handle_unroutable_request(forwarded_rpc(action));
+    assert_eq!(setup.balance(), Some(coins(10)));
+}
+
+#[test]
+fn put_of_data_with_unknown_size_is_rejected() {
+    let mut setup = Setup::new(Config::default(), 10);
+    let requester = setup.client.public_id.clone();
+
+    // The Put is priced before it's paid for, so it's rejected without being charged.
+    assert!(setup
+        .handler
+        .price_put(&requester, &Unserialisable, MessageId::new())
+        .is_none());
+    assert_eq!(setup.balance(), Some(coins(10)));
+    assert!(setup.handler.pending_charges.is_empty());
+
+    let data = setup.new_idata(0);
+    assert_eq!(
+        setup.handler.price_put(&requester, &data, MessageId::new()),
+        Some(*COST_OF_PUT)
+    );
+}
+
+#[test]
+fn full_balance_event_channel_does_not_block_requests() {
+    let mut setup = Setup::new(Config::default(), 10);
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    setup.handler.set_balance_event_sender(sender);
+
+    // Both Puts are charged and forwarded, though there's only room for the first one's event.
+    for value in 0..2 {
+        let request = Request::PutIData(setup.new_idata(value));
+        let _ = forwarded_rpc(setup.send_request(request, MessageId::new()));
+    }
+    assert_eq!(setup.balance(), Some(coins(8)));
+
+    let events = receiver.try_iter().collect::<Vec<_>>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, BalanceEventKind::Withdrawn);
+    assert_eq!(events[0].amount, *COST_OF_PUT);
+    assert_eq!(setup.handler.stats().dropped_balance_events, 1);
+
+    // Once there's room again, events are sent again.
+    let request = Request::PutIData(setup.new_idata(2));
+    let _ = forwarded_rpc(setup.send_request(request, MessageId::new()));
+    assert_eq!(receiver.try_iter().count(), 1);
+    assert_eq!(setup.handler.stats().dropped_balance_events, 1);
+}
+
+#[test]
+fn pending_requests_time_out_and_are_refunded() {
+    let mut config = Config::default();
+    config.set_pending_request_timeout_msec(1_000);
+    let mut setup = Setup::new(config, 10);
+    let message_id = MessageId::new();
+
+    let request = Request::PutIData(setup.new_idata(0));
+    let _ = forwarded_rpc(setup.send_request(request, message_id));
+    let timed_out = Instant::now() + Duration::from_millis(1_000);
+    assert_eq!(setup.balance(), Some(coins(9)));
+
+    // Before the timeout, the charge is kept.
+    setup
+        .handler
+        .expire_pending_charges(timed_out - Duration::from_millis(100));
+    assert_eq!(setup.handler.stats().pending_requests, 1);
+    assert_eq!(setup.balance(), Some(coins(9)));
+
+    // After it, the request is failed and refunded.
+    setup.handler.expire_pending_charges(timed_out);
+    assert_eq!(setup.handler.stats().pending_requests, 0);
+    assert_eq!(setup.balance(), Some(coins(10)));
+
+    // A failure which arrives late isn't refunded again.
+    let response = Rpc::Response {
+        response: Response::Mutation(Err(NdError::from("Put failed"))),
+        requester: setup.client.public_id.clone(),
+        message_id,
+        refund: Some(*COST_OF_PUT),
+    };
+    let _ = setup.handler.handle_vault_rpc(rand::random(), response);
+    assert_eq!(setup.balance(), Some(coins(10)));
+}
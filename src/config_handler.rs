@@ -35,7 +35,15 @@ const CONFIG_FILE: &str = "vault.config";
 const CONNECTION_INFO_FILE: &str = "vault_connection_info.config";
 const DEFAULT_ROOT_DIR_NAME: &str = "root_dir";
 const DEFAULT_MAX_CAPACITY: u64 = 2 * 1024 * 1024 * 1024;
-const ARGS: [&str; 12] = [
+const DEFAULT_IDATA_COPIES: u64 = 3;
+const DEFAULT_MIN_IDATA_COPIES: u64 = 1;
+const DEFAULT_IDATA_CACHE_SIZE: u64 = 32;
+const DEFAULT_MAX_PENDING_REQUESTS: u64 = 10_000;
+const DEFAULT_MAX_PENDING_IDATA_OPS: u64 = 1_000;
+const DEFAULT_MAX_PAID_REQUESTS: u64 = 1_000;
+const DEFAULT_PAID_REQUEST_INTERVAL_MSEC: u64 = 1_000;
+const DEFAULT_PENDING_REQUEST_TIMEOUT_MSEC: u64 = 60_000;
+const ARGS: [&str; 22] = [
     "wallet-address",
     "max-capacity",
     "root-dir",
@@ -48,6 +56,16 @@ const ARGS: [&str; 12] = [
     "keep-alive-interval-msec",
     "our-complete-cert",
     "our-type",
+    "min-idata-copies",
+    "max-object-size",
+    "idata-copies",
+    "idata-cache-size",
+    "min-type-tag",
+    "max-pending-requests",
+    "max-pending-idata-ops",
+    "max-paid-requests",
+    "paid-request-interval-msec",
+    "pending-request-timeout-msec",
 ];
 
 /// Vault configuration
@@ -73,6 +91,45 @@ pub struct Config {
     /// `debug`, `-vvvv` to `trace`. This flag overrides RUST_LOG.
     #[structopt(short, long, parse(from_occurrences))]
     verbose: u64,
+    /// Number of nodes which should each hold a copy of an ImmutableData chunk.  0 is treated as 1.
+    #[structopt(long)]
+    idata_copies: Option<u64>,
+    /// Minimum number of nodes which must be available to hold an ImmutableData chunk for a Put of
+    /// it to be accepted.
+    #[structopt(long)]
+    min_idata_copies: Option<u64>,
+    /// Upper limit in bytes for the serialised size of any single piece of data Put by a client.
+    /// If not set, there is no limit.
+    #[structopt(long)]
+    max_object_size: Option<u64>,
+    /// Maximum number of published ImmutableData chunks to cache in memory for serving Gets.  0
+    /// disables the cache.
+    #[structopt(long)]
+    idata_cache_size: Option<u64>,
+    /// Lowest type tag clients may use when creating MutableData or AppendOnlyData.  Lower tags
+    /// are reserved.  If not set, any tag may be used.
+    #[structopt(long)]
+    min_type_tag: Option<u64>,
+    /// Maximum number of paid client requests which can await responses at any one time.  Further
+    /// paid requests are rejected, without being charged, until some of these complete.
+    #[structopt(long)]
+    max_pending_requests: Option<u64>,
+    /// Maximum number of ImmutableData operations which can await responses from the holders at any
+    /// one time.  Further Puts are rejected, and refunded, until some of these complete.
+    #[structopt(long)]
+    max_pending_idata_ops: Option<u64>,
+    /// Maximum number of paid requests a client, together with its apps, can make in each
+    /// paid-request-interval-msec.  Further requests are rejected without being charged.
+    #[structopt(long)]
+    max_paid_requests: Option<u64>,
+    /// Length in milliseconds of the interval over which each client's paid requests are limited
+    /// to max-paid-requests.
+    #[structopt(long)]
+    paid_request_interval_msec: Option<u64>,
+    /// Time in milliseconds after which a paid client request still awaiting a response is failed,
+    /// and its charge refunded.
+    #[structopt(long)]
+    pending_request_timeout_msec: Option<u64>,
     #[structopt(flatten)]
     #[allow(missing_docs)]
     quic_p2p_config: QuicP2pConfig,
@@ -87,6 +144,16 @@ impl Config {
             max_capacity: None,
             root_dir: None,
             verbose: 0,
+            idata_copies: None,
+            min_idata_copies: None,
+            max_object_size: None,
+            idata_cache_size: None,
+            min_type_tag: None,
+            max_pending_requests: None,
+            max_pending_idata_ops: None,
+            max_paid_requests: None,
+            paid_request_interval_msec: None,
+            pending_request_timeout_msec: None,
             quic_p2p_config: Default::default(),
         });
 
@@ -130,6 +197,113 @@ impl Config {
         self.root_dir = Some(path.into())
     }
 
+    /// Number of nodes which should each hold a copy of an ImmutableData chunk.
+    pub fn idata_copies(&self) -> u64 {
+        self.idata_copies.unwrap_or(DEFAULT_IDATA_COPIES)
+    }
+
+    /// Set the number of nodes which should each hold a copy of an ImmutableData chunk.
+    pub fn set_idata_copies(&mut self, idata_copies: u64) {
+        self.idata_copies = Some(idata_copies)
+    }
+
+    /// Minimum number of nodes which must be available to hold an ImmutableData chunk for a Put of
+    /// it to be accepted.
+    pub fn min_idata_copies(&self) -> u64 {
+        self.min_idata_copies.unwrap_or(DEFAULT_MIN_IDATA_COPIES)
+    }
+
+    /// Set the minimum number of nodes required to hold an ImmutableData chunk.
+    pub fn set_min_idata_copies(&mut self, min_idata_copies: u64) {
+        self.min_idata_copies = Some(min_idata_copies)
+    }
+
+    /// Upper limit in bytes for the serialised size of any single piece of data Put by a client.
+    pub fn max_object_size(&self) -> Option<u64> {
+        self.max_object_size
+    }
+
+    /// Set the upper limit in bytes for the serialised size of data Put by a client.
+    pub fn set_max_object_size(&mut self, max_object_size: u64) {
+        self.max_object_size = Some(max_object_size)
+    }
+
+    /// Maximum number of published ImmutableData chunks to cache in memory for serving Gets.
+    pub fn idata_cache_size(&self) -> u64 {
+        self.idata_cache_size.unwrap_or(DEFAULT_IDATA_CACHE_SIZE)
+    }
+
+    /// Set the maximum number of published ImmutableData chunks to cache.  0 disables the cache.
+    pub fn set_idata_cache_size(&mut self, idata_cache_size: u64) {
+        self.idata_cache_size = Some(idata_cache_size)
+    }
+
+    /// Lowest type tag clients may use when creating MutableData or AppendOnlyData.
+    pub fn min_type_tag(&self) -> Option<u64> {
+        self.min_type_tag
+    }
+
+    /// Set the lowest type tag clients may use when creating MutableData or AppendOnlyData.
+    pub fn set_min_type_tag(&mut self, min_type_tag: u64) {
+        self.min_type_tag = Some(min_type_tag)
+    }
+
+    /// Maximum number of paid client requests which can await responses at any one time.
+    pub fn max_pending_requests(&self) -> u64 {
+        self.max_pending_requests
+            .unwrap_or(DEFAULT_MAX_PENDING_REQUESTS)
+    }
+
+    /// Set the maximum number of paid client requests which can await responses at any one time.
+    pub fn set_max_pending_requests(&mut self, max_pending_requests: u64) {
+        self.max_pending_requests = Some(max_pending_requests)
+    }
+
+    /// Maximum number of ImmutableData operations which can await responses from the holders.
+    pub fn max_pending_idata_ops(&self) -> u64 {
+        self.max_pending_idata_ops
+            .unwrap_or(DEFAULT_MAX_PENDING_IDATA_OPS)
+    }
+
+    /// Set the maximum number of ImmutableData operations which can await responses from the
+    /// holders.
+    pub fn set_max_pending_idata_ops(&mut self, max_pending_idata_ops: u64) {
+        self.max_pending_idata_ops = Some(max_pending_idata_ops)
+    }
+
+    /// Maximum number of paid requests a client, together with its apps, can make per interval.
+    pub fn max_paid_requests(&self) -> u64 {
+        self.max_paid_requests.unwrap_or(DEFAULT_MAX_PAID_REQUESTS)
+    }
+
+    /// Set the maximum number of paid requests a client can make per interval.
+    pub fn set_max_paid_requests(&mut self, max_paid_requests: u64) {
+        self.max_paid_requests = Some(max_paid_requests)
+    }
+
+    /// Length in milliseconds of the interval over which each client's paid requests are limited.
+    pub fn paid_request_interval_msec(&self) -> u64 {
+        self.paid_request_interval_msec
+            .unwrap_or(DEFAULT_PAID_REQUEST_INTERVAL_MSEC)
+    }
+
+    /// Set the length in milliseconds of the interval over which paid requests are limited.
+    pub fn set_paid_request_interval_msec(&mut self, paid_request_interval_msec: u64) {
+        self.paid_request_interval_msec = Some(paid_request_interval_msec)
+    }
+
+    /// Time in milliseconds after which a paid client request awaiting a response is failed.
+    pub fn pending_request_timeout_msec(&self) -> u64 {
+        self.pending_request_timeout_msec
+            .unwrap_or(DEFAULT_PENDING_REQUEST_TIMEOUT_MSEC)
+    }
+
+    /// Set the time in milliseconds after which a paid client request awaiting a response is
+    /// failed.
+    pub fn set_pending_request_timeout_msec(&mut self, pending_request_timeout_msec: u64) {
+        self.pending_request_timeout_msec = Some(pending_request_timeout_msec)
+    }
+
     /// Get the log level.
     pub fn verbose(&self) -> Level {
         match self.verbose {
@@ -173,6 +347,26 @@ impl Config {
             self.quic_p2p_config.ip = Some(unwrap!(value.parse()));
         } else if arg == ARGS[11] {
             self.quic_p2p_config.our_type = unwrap!(value.parse());
+        } else if arg == ARGS[12] {
+            self.min_idata_copies = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[13] {
+            self.max_object_size = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[14] {
+            self.idata_copies = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[15] {
+            self.idata_cache_size = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[16] {
+            self.min_type_tag = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[17] {
+            self.max_pending_requests = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[18] {
+            self.max_pending_idata_ops = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[19] {
+            self.max_paid_requests = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[20] {
+            self.paid_request_interval_msec = Some(unwrap!(value.parse()));
+        } else if arg == ARGS[21] {
+            self.pending_request_timeout_msec = Some(unwrap!(value.parse()));
         } else {
             #[cfg(not(feature = "mock"))]
             {
@@ -279,9 +473,9 @@ mod test {
     #[test]
     fn smoke() {
         let expected_size = if cfg!(target_pointer_width = "64") {
-            240
+            400
         } else {
-            152
+            272
         };
         assert_eq!(
             expected_size,
@@ -309,6 +503,16 @@ mod test {
             ["keep-alive-interval-msec", "1"],
             ["our-complete-cert", cert_str.as_str()],
             ["our-type", "client"],
+            ["min-idata-copies", "1"],
+            ["max-object-size", "1"],
+            ["idata-copies", "1"],
+            ["idata-cache-size", "1"],
+            ["min-type-tag", "1"],
+            ["max-pending-requests", "1"],
+            ["max-pending-idata-ops", "1"],
+            ["max-paid-requests", "1"],
+            ["paid-request-interval-msec", "1"],
+            ["pending-request-timeout-msec", "1"],
         ];
 
         for arg in &ARGS {
@@ -327,6 +531,16 @@ mod test {
                 max_capacity: None,
                 root_dir: None,
                 verbose: 0,
+                idata_copies: None,
+                min_idata_copies: None,
+                max_object_size: None,
+                idata_cache_size: None,
+                min_type_tag: None,
+                max_pending_requests: None,
+                max_pending_idata_ops: None,
+                max_paid_requests: None,
+                paid_request_interval_msec: None,
+                pending_request_timeout_msec: None,
                 quic_p2p_config: Default::default(),
             };
             let empty_config = config.clone();
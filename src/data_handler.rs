@@ -7,12 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod adata_handler;
+mod idata_cache;
 mod idata_handler;
 mod idata_holder;
 mod idata_op;
 mod mdata_handler;
 
-use crate::{action::Action, rpc::Rpc, vault::Init, Config, Result};
+use crate::{action::Action, rpc::Rpc, utils, vault::Init, Config, Result};
 use adata_handler::ADataHandler;
 use idata_handler::IDataHandler;
 use idata_holder::IDataHolder;
@@ -24,6 +25,7 @@ use safe_nd::{IData, IDataAddress, MessageId, NodePublicId, PublicId, Request, R
 
 use std::{
     cell::Cell,
+    collections::BTreeSet,
     fmt::{self, Display, Formatter},
     rc::Rc,
 };
@@ -56,6 +58,11 @@ impl DataHandler {
         })
     }
 
+    /// Returns the nodes currently registered as holding the ImmutableData chunk at `address`.
+    pub fn idata_holders_of(&self, address: &IDataAddress) -> BTreeSet<XorName> {
+        self.idata_handler.holders_of(address)
+    }
+
     pub fn handle_vault_rpc(&mut self, src: XorName, rpc: Rpc) -> Option<Action> {
         match rpc {
             Rpc::Request {
@@ -80,10 +87,10 @@ impl DataHandler {
     ) -> Option<Action> {
         use Request::*;
         trace!(
-            "{}: Received ({:?} {:?}) from src {} (client {:?})",
+            "{}: {}: Received {:?} from src {} (client {:?})",
             self,
+            utils::LogId(message_id),
             request,
-            message_id,
             src,
             requester
         );
@@ -282,8 +289,10 @@ impl DataHandler {
             | TransferCoins { .. }
             | DelAuthKey { .. } => {
                 error!(
-                    "{}: Should not receive {:?} as a data handler.",
-                    self, request
+                    "{}: {}: Should not receive {:?} as a data handler.",
+                    self,
+                    utils::LogId(message_id),
+                    request
                 );
                 None
             }
@@ -298,10 +307,10 @@ impl DataHandler {
     ) -> Option<Action> {
         use Response::*;
         trace!(
-            "{}: Received ({:?} {:?}) from {}",
+            "{}: {}: Received {:?} from {}",
             self,
+            utils::LogId(message_id),
             response,
-            message_id,
             src
         );
         match response {
@@ -338,8 +347,10 @@ impl DataHandler {
             | ListAuthKeysAndVersion(_)
             | GetLoginPacket(_) => {
                 error!(
-                    "{}: Should not receive {:?} as a data handler.",
-                    self, response
+                    "{}: {}: Should not receive {:?} as a data handler.",
+                    self,
+                    utils::LogId(message_id),
+                    response
                 );
                 None
             }
@@ -389,7 +389,11 @@ impl ADataHandler {
                     }
                     _ => {
                         return {
-                            error!("{}: Unexpected chunk encountered", own_id);
+                            error!(
+                                "{}: {}: Unexpected chunk encountered",
+                                own_id,
+                                utils::LogId(message_id)
+                            );
                             Err(NdError::InvalidOperation)
                         }
                     }
@@ -422,7 +426,11 @@ impl ADataHandler {
                         unpub_unseq_data.append_permissions(permissions, permissions_idx)?;
                     }
                     _ => {
-                        error!("{}: Unexpected chunk encountered", own_id);
+                        error!(
+                            "{}: {}: Unexpected chunk encountered",
+                            own_id,
+                            utils::LogId(message_id)
+                        );
                         return Err(NdError::InvalidOperation);
                     }
                 }
@@ -475,7 +483,11 @@ impl ADataHandler {
                     AData::PubSeq(ref mut adata) => adata.append(append.values, index)?,
                     AData::UnpubSeq(ref mut adata) => adata.append(append.values, index)?,
                     AData::PubUnseq(_) | AData::UnpubUnseq(_) => {
-                        error!("{}: Unexpected unseqential chunk encountered", own_id);
+                        error!(
+                            "{}: {}: Unexpected unseqential chunk encountered",
+                            own_id,
+                            utils::LogId(message_id)
+                        );
                         return Err(NdError::InvalidOperation);
                     }
                 }
@@ -502,7 +514,11 @@ impl ADataHandler {
                     AData::PubUnseq(ref mut adata) => adata.append(operation.values)?,
                     AData::UnpubUnseq(ref mut adata) => adata.append(operation.values)?,
                     AData::PubSeq(_) | AData::UnpubSeq(_) => {
-                        error!("{}: Unexpected sequential chunk encountered", own_id);
+                        error!(
+                            "{}: {}: Unexpected sequential chunk encountered",
+                            own_id,
+                            utils::LogId(message_id)
+                        );
                         return Err(NdError::InvalidOperation);
                     }
                 }
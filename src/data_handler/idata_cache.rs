@@ -0,0 +1,109 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safe_nd::{IData, IDataAddress};
+use std::collections::{BTreeMap, VecDeque};
+
+/// A bounded cache of published ImmutableData chunks, evicting the least recently used first.
+///
+/// Unpublished chunks are never cached, as they may only be retrieved by their owner and can be
+/// deleted.
+pub(super) struct IDataCache {
+    capacity: usize,
+    entries: BTreeMap<IDataAddress, IData>,
+    // Addresses of the cached chunks, least recently used first.
+    usage: VecDeque<IDataAddress>,
+}
+
+impl IDataCache {
+    /// Returns a cache holding at most `capacity` chunks.  A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+            usage: Default::default(),
+        }
+    }
+
+    /// Returns a copy of the chunk at `address` if it's cached, marking it as recently used.
+    pub fn get(&mut self, address: &IDataAddress) -> Option<IData> {
+        let data = self.entries.get(address)?.clone();
+        self.touch(address);
+        Some(data)
+    }
+
+    /// Caches `data` if it's published, evicting the least recently used chunk if full.
+    pub fn insert(&mut self, data: IData) {
+        if self.capacity == 0 || !data.is_pub() {
+            return;
+        }
+
+        let address = *data.address();
+        if self.entries.insert(address, data).is_some() {
+            self.touch(&address);
+            return;
+        }
+        self.usage.push_back(address);
+        while self.usage.len() > self.capacity {
+            if let Some(evicted) = self.usage.pop_front() {
+                let _ = self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    // Moves `address` to the most recently used end of `usage`.
+    fn touch(&mut self, address: &IDataAddress) {
+        if let Some(index) = self.usage.iter().position(|cached| cached == address) {
+            if let Some(address) = self.usage.remove(index) {
+                self.usage.push_back(address);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use safe_nd::{ClientFullId, PubImmutableData, UnpubImmutableData};
+
+    fn pub_idata(value: u8) -> IData {
+        IData::Pub(PubImmutableData::new(vec![value]))
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let mut cache = IDataCache::new(2);
+        let (data_0, data_1, data_2) = (pub_idata(0), pub_idata(1), pub_idata(2));
+
+        cache.insert(data_0.clone());
+        cache.insert(data_1.clone());
+        // Using `data_0` leaves `data_1` as the least recently used.
+        assert_eq!(cache.get(data_0.address()), Some(data_0.clone()));
+        cache.insert(data_2.clone());
+
+        assert_eq!(cache.get(data_0.address()), Some(data_0));
+        assert_eq!(cache.get(data_1.address()), None);
+        assert_eq!(cache.get(data_2.address()), Some(data_2));
+    }
+
+    #[test]
+    fn only_published_data_is_cached() {
+        let mut cache = IDataCache::new(2);
+        let owner = *ClientFullId::new_ed25519(&mut rand::thread_rng())
+            .public_id()
+            .public_key();
+        let data = IData::Unpub(UnpubImmutableData::new(vec![0], owner));
+        cache.insert(data.clone());
+        assert_eq!(cache.get(data.address()), None);
+
+        let mut cache = IDataCache::new(0);
+        let data = pub_idata(0);
+        cache.insert(data.clone());
+        assert_eq!(cache.get(data.address()), None);
+    }
+}
@@ -6,16 +6,17 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{IDataOp, IDataRequest, OpType};
+use super::{idata_cache::IDataCache, IDataOp, IDataRequest, OpType};
 use crate::{action::Action, rpc::Rpc, utils, vault::Init, Config, Result, ToDbKey};
 use log::{trace, warn};
 use pickledb::PickleDb;
 use safe_nd::{
-    Error as NdError, IData, IDataAddress, MessageId, NodePublicId, PublicId, Response,
+    Error as NdError, IData, IDataAddress, MessageId, NodePublicId, PublicId, Request, Response,
     Result as NdResult, XorName,
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Ordering,
     collections::{btree_map::Entry, BTreeMap, BTreeSet},
     fmt::{self, Display, Formatter},
     iter,
@@ -23,8 +24,6 @@ use std::{
 
 const IMMUTABLE_META_DB_NAME: &str = "immutable_data.db";
 const FULL_ADULTS_DB_NAME: &str = "full_adults.db";
-// The number of separate copies of an ImmutableData chunk which should be maintained.
-const IMMUTABLE_DATA_COPY_COUNT: usize = 3;
 
 #[derive(Default, Serialize, Deserialize)]
 struct ChunkMetadata {
@@ -34,9 +33,18 @@ struct ChunkMetadata {
 pub(super) struct IDataHandler {
     id: NodePublicId,
     idata_ops: BTreeMap<MessageId, IDataOp>,
+    // The maximum number of operations which can be awaiting responses from the holders at any one
+    // time.  Further Put requests are rejected (and refunded) until some of these complete.
+    max_pending_ops: usize,
+    // The number of separate copies of an ImmutableData chunk which should be maintained.
+    copy_count: usize,
+    // The minimum number of holders for a chunk below which Puts are rejected.
+    min_copies: usize,
     metadata: PickleDb,
-    #[allow(unused)]
+    // Nodes which have failed to store a chunk, assumed to be out of space.
     full_adults: PickleDb,
+    // Published chunks recently retrieved from their holders.
+    cache: IDataCache,
 }
 
 impl IDataHandler {
@@ -45,11 +53,34 @@ impl IDataHandler {
         let metadata = utils::new_db(&root_dir, IMMUTABLE_META_DB_NAME, init_mode)?;
         let full_adults = utils::new_db(&root_dir, FULL_ADULTS_DB_NAME, init_mode)?;
 
+        let mut copy_count = config.idata_copies() as usize;
+        if copy_count == 0 {
+            // Otherwise Puts would be sent to no holders, and so never be answered.
+            warn!("{}: idata-copies is 0.  Using 1 instead.", id.name());
+            copy_count = 1;
+        }
+        let mut min_copies = config.min_idata_copies() as usize;
+        if min_copies > copy_count {
+            // Otherwise every Put would be rejected for lacking holders.
+            warn!(
+                "{}: min-idata-copies ({}) exceeds idata-copies ({}).  Using {} instead.",
+                id.name(),
+                min_copies,
+                copy_count,
+                copy_count
+            );
+            min_copies = copy_count;
+        }
+
         Ok(Self {
             id,
             idata_ops: Default::default(),
+            max_pending_ops: config.max_pending_idata_ops() as usize,
+            copy_count,
+            min_copies,
             metadata,
             full_adults,
+            cache: IDataCache::new(config.idata_cache_size() as usize),
         })
     }
 
@@ -80,8 +111,9 @@ impl IDataHandler {
         if self.metadata.exists(&(*data.address()).to_db_key()) {
             return if data.is_pub() {
                 trace!(
-                    "{}: Replying success for Put {:?}, it already exists.",
+                    "{}: {}: Replying success for Put {:?}, it already exists.",
                     self,
+                    utils::LogId(message_id),
                     data
                 );
                 respond(Ok(()))
@@ -92,28 +124,66 @@ impl IDataHandler {
             };
         }
 
-        let target_holders = self
-            .non_full_adults_sorted(data.name())
-            .chain(self.elders_sorted(data.name()))
-            .take(IMMUTABLE_DATA_COPY_COUNT)
-            .cloned()
+        if self.idata_ops.len() >= self.max_pending_ops {
+            warn!(
+                "{}: {}: Rejecting Put {:?}: {} operations are already pending.",
+                self,
+                utils::LogId(message_id),
+                data.address(),
+                self.idata_ops.len()
+            );
+            return respond(Err(NdError::from(
+                "Network busy: too many pending operations",
+            )));
+        }
+
+        let target_holders = intended_holders(data.name(), self.non_full_adults(), self.copy_count)
+            .into_iter()
+            .chain(intended_holders(
+                data.name(),
+                self.elders(),
+                self.copy_count,
+            ))
+            .take(self.copy_count)
             .collect::<BTreeSet<_>>();
-        let data_name = *data.name();
+        if target_holders.len() < self.min_copies {
+            warn!(
+                "{}: {}: Rejecting Put {:?}: only {} of the required {} holders are available.",
+                self,
+                utils::LogId(message_id),
+                data.address(),
+                target_holders.len(),
+                self.min_copies
+            );
+            return respond(Err(NdError::from(
+                "Network too small: not enough nodes to hold the data",
+            )));
+        }
+        if target_holders.len() < self.copy_count {
+            warn!(
+                "{}: {}: Only {} of the intended {} holders are available for {:?}.",
+                self,
+                utils::LogId(message_id),
+                target_holders.len(),
+                self.copy_count,
+                data.address()
+            );
+        }
         let idata_op = IDataOp::new(
             requester.clone(),
-            IDataRequest::PutIData(data),
+            IDataRequest::PutIData(*data.address()),
             target_holders.clone(),
         );
 
         match self.idata_ops.entry(message_id) {
             Entry::Occupied(_) => respond(Err(NdError::DuplicateMessageId)),
             Entry::Vacant(vacant_entry) => {
-                let idata_op = vacant_entry.insert(idata_op);
+                let _ = vacant_entry.insert(idata_op);
                 Some(Action::SendToPeers {
                     sender: data_name,
                     targets: target_holders,
                     rpc: Rpc::Request {
-                        request: idata_op.request(),
+                        request: Request::PutIData(data),
                         requester,
                         message_id,
                     },
@@ -154,12 +224,12 @@ impl IDataHandler {
         match self.idata_ops.entry(message_id) {
             Entry::Occupied(_) => respond(Err(NdError::DuplicateMessageId)),
             Entry::Vacant(vacant_entry) => {
-                let idata_op = vacant_entry.insert(idata_op);
+                let _ = vacant_entry.insert(idata_op);
                 Some(Action::SendToPeers {
                     sender: *address.name(),
                     targets: metadata.holders,
                     rpc: Rpc::Request {
-                        request: idata_op.request(),
+                        request: Request::DeleteUnpubIData(address),
                         requester,
                         message_id,
                     },
@@ -187,6 +257,16 @@ impl IDataHandler {
             })
         };
 
+        if let Some(data) = self.cache.get(&address) {
+            trace!(
+                "{}: {}: Serving {:?} from the cache.",
+                self,
+                utils::LogId(message_id),
+                address
+            );
+            return respond(Ok(data));
+        }
+
         // We're acting as data handler, received request from client handlers
         let metadata = match self.get_metadata_for(address) {
             Ok(metadata) => metadata,
@@ -201,12 +281,12 @@ impl IDataHandler {
         match self.idata_ops.entry(message_id) {
             Entry::Occupied(_) => respond(Err(NdError::DuplicateMessageId)),
             Entry::Vacant(vacant_entry) => {
-                let idata_op = vacant_entry.insert(idata_op);
+                let _ = vacant_entry.insert(idata_op);
                 Some(Action::SendToPeers {
                     sender: *address.name(),
                     targets: metadata.holders,
                     rpc: Rpc::Request {
-                        request: idata_op.request(),
+                        request: Request::GetIData(address),
                         requester,
                         message_id,
                     },
@@ -240,49 +320,75 @@ impl IDataHandler {
         &mut self,
         idata_address: IDataAddress,
         sender: XorName,
-        _result: NdResult<()>,
+        result: NdResult<()>,
         message_id: MessageId,
     ) -> Option<Action> {
         // TODO -
-        // - if Ok, and this is the final of the three responses send success back to client handlers and
-        //   then on to the client.  Note: there's no functionality in place yet to know whether
-        //   this is the last response or not.
-        // - if Ok, and this is not the last response, just return `None` here.
-        // - if Err, we need to flag this sender as "full" (i.e. add to self.full_adults, try on
-        //   next closest non-full adult, or elder if none.  Also update the metadata for this
-        //   chunk.  Not known yet where we'll get the chunk from to do that.
-        //
-        // For phase 1, we can leave many of these unanswered.
-
-        // TODO - we'll assume `result` is success for phase 1.
-        let db_key = idata_address.to_db_key();
-        let mut metadata = self
-            .metadata
-            .get::<ChunkMetadata>(&db_key)
-            .unwrap_or_default();
-        if !metadata.holders.insert(sender) {
+        // - if Err, try on next closest non-full adult, or elder if none.  The data isn't kept
+        //   while the holders store it, so this needs the chunk to be fetched again first.
+        if let Err(error) = result {
             warn!(
-                "{}: {} already registered as a holder for {:?}",
+                "{}: {}: {} failed to store {:?}: {}",
                 self,
+                utils::LogId(message_id),
                 sender,
-                self.idata_op(&message_id)?
+                idata_address,
+                error
             );
-        }
-        if let Err(error) = self.metadata.set(&db_key, &metadata) {
-            warn!("{}: Failed to write metadata to DB: {:?}", self, error);
-            // TODO - send failure back to client handlers (hopefully won't accumulate), or
-            //        maybe self-terminate if we can't fix this error?
+            if let Err(error) = self.full_adults.set(&sender.to_db_key(), &()) {
+                warn!(
+                    "{}: {}: Failed to write full adult to DB: {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    error
+                );
+            }
+        } else {
+            let db_key = idata_address.to_db_key();
+            let mut metadata = self
+                .metadata
+                .get::<ChunkMetadata>(&db_key)
+                .unwrap_or_default();
+            if !metadata.holders.insert(sender) {
+                warn!(
+                    "{}: {}: {} already registered as a holder for {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    sender,
+                    self.idata_op(&message_id)?
+                );
+            }
+            if let Err(error) = self.metadata.set(&db_key, &metadata) {
+                warn!(
+                    "{}: {}: Failed to write metadata to DB: {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    error
+                );
+                // TODO - send failure back to client handlers (hopefully won't accumulate), or
+                //        maybe self-terminate if we can't fix this error?
+            }
         }
 
         self.remove_idata_op_if_concluded(&message_id)
-            .map(|idata_op| Action::RespondToClientHandlers {
-                sender: *idata_address.name(),
-                rpc: Rpc::Response {
-                    requester: idata_op.client().clone(),
-                    response: Response::Mutation(Ok(())),
-                    message_id,
-                    refund: None,
-                },
+            .map(|idata_op| {
+                // The Put only fails if none of the holders stored the chunk, in which case the
+                // client is refunded.
+                let errors = idata_op.get_any_errors();
+                let result = match errors.values().next() {
+                    Some(error) if errors.len() == idata_op.rpc_states.len() => Err(error.clone()),
+                    _ => Ok(()),
+                };
+                let refund = utils::get_refund_for_put(&result);
+                Action::RespondToClientHandlers {
+                    sender: *idata_address.name(),
+                    rpc: Rpc::Response {
+                        requester: idata_op.client().clone(),
+                        response: Response::Mutation(result),
+                        message_id,
+                        refund,
+                    },
+                }
             })
     }
 
@@ -296,13 +402,20 @@ impl IDataHandler {
         // TODO - Only rudimentary checks for if requests to Adult nodes were successful. These
         // mostly assume we're in practice only delegating to a single Adult (ourself in phase 1).
         if let Err(err) = result {
-            warn!("{}: Node reports error deleting: {}", self, err);
+            warn!(
+                "{}: {}: Node reports error deleting: {}",
+                self,
+                utils::LogId(message_id),
+                err
+            );
         } else {
             let db_key = idata_address.to_db_key();
             let metadata = self.metadata.get::<ChunkMetadata>(&db_key).or_else(|| {
                 warn!(
-                    "{}: Failed to get metadata from DB: {:?}",
-                    self, idata_address
+                    "{}: {}: Failed to get metadata from DB: {:?}",
+                    self,
+                    utils::LogId(message_id),
+                    idata_address
                 );
                 None
             });
@@ -310,19 +423,30 @@ impl IDataHandler {
             if let Some(mut metadata) = metadata {
                 if !metadata.holders.remove(&sender) {
                     warn!(
-                        "{}: {} is not registered as a holder for {:?}",
+                        "{}: {}: {} is not registered as a holder for {:?}",
                         self,
+                        utils::LogId(message_id),
                         sender,
                         self.idata_op(&message_id)?
                     );
                 }
                 if metadata.holders.is_empty() {
                     if let Err(error) = self.metadata.rem(&db_key) {
-                        warn!("{}: Failed to delete metadata from DB: {:?}", self, error);
+                        warn!(
+                            "{}: {}: Failed to delete metadata from DB: {:?}",
+                            self,
+                            utils::LogId(message_id),
+                            error
+                        );
                         // TODO - Send failure back to client handlers?
                     }
                 } else if let Err(error) = self.metadata.set(&db_key, &metadata) {
-                    warn!("{}: Failed to write metadata to DB: {:?}", self, error);
+                    warn!(
+                        "{}: {}: Failed to write metadata to DB: {:?}",
+                        self,
+                        utils::LogId(message_id),
+                        error
+                    );
                     // TODO - Send failure back to client handlers?
                 }
             };
@@ -367,9 +491,30 @@ impl IDataHandler {
             idata_op.handle_get_idata_resp(sender, result, own_id, message_id)
         });
         let _ = self.remove_idata_op_if_concluded(&message_id);
+
+        if let Some(Action::RespondToClientHandlers {
+            rpc:
+                Rpc::Response {
+                    response: Response::GetIData(Ok(ref data)),
+                    ..
+                },
+            ..
+        }) = action
+        {
+            self.cache.insert(data.clone());
+        }
         action
     }
 
+    /// Returns the nodes currently registered as holding the chunk at `address`, or an empty set if
+    /// the chunk is unknown.
+    pub(super) fn holders_of(&self, address: &IDataAddress) -> BTreeSet<XorName> {
+        self.metadata
+            .get::<ChunkMetadata>(&address.to_db_key())
+            .map(|metadata| metadata.holders)
+            .unwrap_or_default()
+    }
+
     fn get_metadata_for(&self, address: IDataAddress) -> NdResult<ChunkMetadata> {
         match self.metadata.get::<ChunkMetadata>(&address.to_db_key()) {
             Some(metadata) => {
@@ -390,8 +535,9 @@ impl IDataHandler {
     pub(super) fn idata_op(&self, message_id: &MessageId) -> Option<&IDataOp> {
         self.idata_ops.get(message_id).or_else(|| {
             warn!(
-                "{}: No current ImmutableData operation for {:?}",
-                self, message_id
+                "{}: {}: No current ImmutableData operation",
+                self,
+                utils::LogId(*message_id)
             );
             None
         })
@@ -401,8 +547,9 @@ impl IDataHandler {
         let own_id = format!("{}", self);
         self.idata_ops.get_mut(message_id).or_else(|| {
             warn!(
-                "{}: No current ImmutableData operation for {:?}",
-                own_id, message_id
+                "{}: {}: No current ImmutableData operation",
+                own_id,
+                utils::LogId(*message_id)
             );
             None
         })
@@ -420,20 +567,346 @@ impl IDataHandler {
         None
     }
 
-    // Returns an iterator over all of our section's non-full adults' names, sorted by closest to
-    // `target`.
-    fn non_full_adults_sorted(&self, _target: &XorName) -> impl Iterator<Item = &XorName> {
+    // Returns an iterator over all of our section's non-full adults' names.
+    fn non_full_adults(&self) -> impl Iterator<Item = &XorName> {
         None.iter()
     }
 
-    // Returns an iterator over all of our section's elders' names, sorted by closest to `target`.
-    fn elders_sorted(&self, _target: &XorName) -> impl Iterator<Item = &XorName> {
+    // Returns an iterator over all of our section's elders' names.
+    fn elders(&self) -> impl Iterator<Item = &XorName> {
         iter::once(self.id.name())
     }
 }
 
+/// Returns the (at most `count`) members of `group` which should hold the chunk called `name`,
+/// closest first.
+///
+/// Members are ordered by XOR distance to `name`.  Distinct names are never the same distance from
+/// `name`, so this is a strict total order and the result only depends on `name` and the membership
+/// of `group` (not on the order in which the members are given).  Every node with the same view of
+/// the group hence computes the same holders, and when a holder leaves, the same replacement: the
+/// next member in this ordering.
+pub(super) fn intended_holders<'a, I>(name: &XorName, group: I, count: usize) -> Vec<XorName>
+where
+    I: IntoIterator<Item = &'a XorName>,
+{
+    let mut holders = group.into_iter().cloned().collect::<Vec<_>>();
+    // No tie-break is needed: `a ^ name == b ^ name` implies `a == b`, so only duplicates tie.
+    holders.sort_by(|lhs, rhs| cmp_distance(name, lhs, rhs));
+    holders.dedup();
+    holders.truncate(count);
+    holders
+}
+
+// Compares the XOR distances of `lhs` and `rhs` to `target`.
+fn cmp_distance(target: &XorName, lhs: &XorName, rhs: &XorName) -> Ordering {
+    target
+        .0
+        .iter()
+        .zip(lhs.0.iter().zip(rhs.0.iter()))
+        .map(|(target, (lhs, rhs))| (lhs ^ target).cmp(&(rhs ^ target)))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
 impl Display for IDataHandler {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(formatter, "{}", self.id.name())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{seq::SliceRandom, Rng};
+    use safe_nd::{NodeFullId, PubImmutableData};
+    use tempdir::TempDir;
+    use unwrap::unwrap;
+
+    const COPY_COUNT: usize = 3;
+
+    fn new_handler(root_dir: &TempDir, min_idata_copies: u64) -> IDataHandler {
+        let mut config = Config::default();
+        config.set_min_idata_copies(min_idata_copies);
+        new_handler_with_config(root_dir, config)
+    }
+
+    fn new_handler_with_config(root_dir: &TempDir, mut config: Config) -> IDataHandler {
+        config.set_root_dir(root_dir.path());
+        let id = NodeFullId::new(&mut rand::thread_rng());
+        unwrap!(IDataHandler::new(
+            id.public_id().clone(),
+            &config,
+            Init::New
+        ))
+    }
+
+    #[test]
+    fn put_rejected_without_min_copies() {
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut handler = new_handler(&root_dir, 2);
+        let requester = PublicId::Node(handler.id.clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+
+        // Only this node is available to hold the data, so the Put is rejected and refunded.
+        match handler.handle_put_idata_req(requester.clone(), data.clone(), MessageId::new()) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::Mutation(Err(_)),
+                        refund: Some(_),
+                        ..
+                    },
+                ..
+            }) => (),
+            _ => panic!("Put should have been rejected with a refund"),
+        }
+        assert!(handler.idata_ops.is_empty());
+        assert!(handler.holders_of(data.address()).is_empty());
+
+        // With the requirement met, the Put is sent to the holder.
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut handler = new_handler(&root_dir, 1);
+        match handler.handle_put_idata_req(requester, data, MessageId::new()) {
+            Some(Action::SendToPeers { targets, .. }) => assert_eq!(targets.len(), 1),
+            _ => panic!("Put should have been sent to the holder"),
+        }
+    }
+
+    #[test]
+    fn put_with_configured_copy_count() {
+        let requester =
+            PublicId::Node(NodeFullId::new(&mut rand::thread_rng()).public_id().clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+
+        // Only this node is available to hold the data.  More copies than that are stored on the
+        // holders there are, and a single copy on this node.
+        for idata_copies in &[3, 1] {
+            let root_dir = unwrap!(TempDir::new("idata_handler"));
+            let mut config = Config::default();
+            config.set_idata_copies(*idata_copies);
+            let mut handler = new_handler_with_config(&root_dir, config);
+            match handler.handle_put_idata_req(requester.clone(), data.clone(), MessageId::new()) {
+                Some(Action::SendToPeers { targets, .. }) => {
+                    assert_eq!(
+                        targets,
+                        iter::once(*handler.id.name()).collect::<BTreeSet<_>>()
+                    )
+                }
+                _ => panic!("Put should have been sent to the holder"),
+            }
+        }
+
+        // A minimum above the number of copies is lowered to it, rather than rejecting every Put.
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut config = Config::default();
+        config.set_idata_copies(1);
+        config.set_min_idata_copies(2);
+        let mut handler = new_handler_with_config(&root_dir, config);
+        assert_eq!(handler.min_copies, 1);
+        match handler.handle_put_idata_req(requester, data, MessageId::new()) {
+            Some(Action::SendToPeers { targets, .. }) => assert_eq!(targets.len(), 1),
+            _ => panic!("Put should have been sent to the holder"),
+        }
+    }
+
+    #[test]
+    fn put_with_zero_copy_count() {
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut config = Config::default();
+        config.set_idata_copies(0);
+        config.set_min_idata_copies(0);
+        let mut handler = new_handler_with_config(&root_dir, config);
+        let requester = PublicId::Node(handler.id.clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+
+        // At least one copy is always stored, so the Put still reaches a holder.
+        assert_eq!(handler.copy_count, 1);
+        match handler.handle_put_idata_req(requester, data, MessageId::new()) {
+            Some(Action::SendToPeers { targets, .. }) => assert_eq!(targets.len(), 1),
+            _ => panic!("Put should have been sent to the holder"),
+        }
+        assert_eq!(handler.idata_ops.len(), 1);
+    }
+
+    #[test]
+    fn put_failed_by_holder() {
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut handler = new_handler(&root_dir, 1);
+        let holder = *handler.id.name();
+        let requester = PublicId::Node(handler.id.clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+        let message_id = MessageId::new();
+
+        match handler.handle_put_idata_req(requester, data.clone(), message_id) {
+            Some(Action::SendToPeers { .. }) => (),
+            _ => panic!("Put should have been sent to the holder"),
+        }
+
+        // The only holder is out of space, so the Put fails and is refunded.  The holder isn't
+        // registered for the chunk, and is recorded as full.
+        let error = NdError::from("Not enough space");
+        match handler.handle_mutation_resp(holder, Err(error), message_id) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::Mutation(Err(_)),
+                        refund: Some(_),
+                        ..
+                    },
+                ..
+            }) => (),
+            _ => panic!("Put should have failed with a refund"),
+        }
+        assert!(handler.idata_ops.is_empty());
+        assert!(handler.holders_of(data.address()).is_empty());
+        assert!(handler.full_adults.exists(&holder.to_db_key()));
+    }
+
+    #[test]
+    fn put_rejected_when_too_many_ops_pending() {
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut config = Config::default();
+        config.set_max_pending_idata_ops(2);
+        let mut handler = new_handler_with_config(&root_dir, config);
+        let requester = PublicId::Node(handler.id.clone());
+
+        // Fill the queue with Puts which the holder hasn't answered yet.
+        for value in 0..2 {
+            let data = IData::Pub(PubImmutableData::new(vec![value]));
+            match handler.handle_put_idata_req(requester.clone(), data, MessageId::new()) {
+                Some(Action::SendToPeers { .. }) => (),
+                _ => panic!("Put should have been sent to the holder"),
+            }
+        }
+        assert_eq!(handler.idata_ops.len(), 2);
+
+        let data = IData::Pub(PubImmutableData::new(vec![2]));
+        match handler.handle_put_idata_req(requester, data, MessageId::new()) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::Mutation(Err(_)),
+                        refund: Some(_),
+                        ..
+                    },
+                ..
+            }) => (),
+            _ => panic!("Put should have been rejected with a refund"),
+        }
+        assert_eq!(handler.idata_ops.len(), 2);
+    }
+
+    #[test]
+    fn intended_holders_are_reproducible() {
+        let mut rng = rand::thread_rng();
+        let name: XorName = rng.gen();
+        let mut group = (0..10).map(|_| rng.gen()).collect::<Vec<XorName>>();
+
+        let holders = intended_holders(&name, &group, COPY_COUNT);
+        assert_eq!(holders.len(), COPY_COUNT);
+
+        group.shuffle(&mut rng);
+        assert_eq!(holders, intended_holders(&name, &group, COPY_COUNT));
+
+        // The chosen holders are closer to `name` than every other member of the group.
+        for member in group.iter().filter(|member| !holders.contains(*member)) {
+            for holder in &holders {
+                assert_eq!(cmp_distance(&name, holder, member), Ordering::Less);
+            }
+        }
+    }
+
+    #[test]
+    fn intended_holders_of_small_group() {
+        let mut rng = rand::thread_rng();
+        let name: XorName = rng.gen();
+        let member: XorName = rng.gen();
+
+        assert!(intended_holders(&name, iter::empty(), COPY_COUNT).is_empty());
+        assert_eq!(
+            intended_holders(&name, &[member, member], COPY_COUNT),
+            vec![member]
+        );
+    }
+
+    #[test]
+    fn intended_holders_count() {
+        let mut rng = rand::thread_rng();
+        let name: XorName = rng.gen();
+        let group = (0..10).map(|_| rng.gen()).collect::<Vec<XorName>>();
+
+        // The closest `count` members are chosen, consistently for any count.
+        let all_members = intended_holders(&name, &group, group.len());
+        assert_eq!(all_members.len(), group.len());
+        for count in 0..=group.len() {
+            assert_eq!(
+                intended_holders(&name, &group, count),
+                &all_members[..count]
+            );
+        }
+    }
+
+    #[test]
+    fn replacement_holder_is_agreed() {
+        let mut rng = rand::thread_rng();
+        let name: XorName = rng.gen();
+        let mut group = (0..10).map(|_| rng.gen()).collect::<Vec<XorName>>();
+        let holders = intended_holders(&name, &group, COPY_COUNT);
+
+        // The closest holder leaves the group.
+        let departed = holders[0];
+        group.retain(|member| *member != departed);
+
+        // Each remaining member computes the new holders from its own ordering of the group.
+        let new_holders = (0..group.len())
+            .map(|_| {
+                group.shuffle(&mut rng);
+                intended_holders(&name, &group, COPY_COUNT)
+            })
+            .collect::<BTreeSet<_>>();
+        assert_eq!(new_holders.len(), 1);
+
+        let new_holders = unwrap!(new_holders.into_iter().next());
+        assert_eq!(new_holders.len(), COPY_COUNT);
+        assert!(!new_holders.contains(&departed));
+        assert_eq!(&new_holders[..COPY_COUNT - 1], &holders[1..]);
+    }
+
+    #[test]
+    fn repeated_get_is_served_from_cache() {
+        let root_dir = unwrap!(TempDir::new("idata_handler"));
+        let mut handler = new_handler(&root_dir, 1);
+        let requester = PublicId::Node(handler.id.clone());
+        let holder = *handler.id.name();
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+
+        let message_id = MessageId::new();
+        let _ = handler.handle_put_idata_req(requester.clone(), data.clone(), message_id);
+        let _ = handler.handle_mutation_resp(holder, Ok(()), message_id);
+
+        // The first Get is sent to the holder, and its response is cached.
+        let message_id = MessageId::new();
+        match handler.handle_get_idata_req(requester.clone(), *data.address(), message_id) {
+            Some(Action::SendToPeers { .. }) => (),
+            _ => panic!("First Get should have been sent to the holder"),
+        }
+        assert!(handler
+            .handle_get_idata_resp(holder, Ok(data.clone()), message_id)
+            .is_some());
+
+        // The second Get is answered without involving the holder.
+        match handler.handle_get_idata_req(requester, *data.address(), MessageId::new()) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::GetIData(result),
+                        ..
+                    },
+                ..
+            }) => assert_eq!(unwrap!(result), data),
+            _ => panic!("Second Get should have been served from the cache"),
+        }
+        assert!(handler.idata_ops.is_empty());
+    }
+}
@@ -50,8 +50,9 @@ impl IDataHolder {
     ) -> Option<Action> {
         let result = if self.chunks.has(data.address()) {
             info!(
-                "{}: Immutable chunk already exists, not storing: {:?}",
+                "{}: {}: Immutable chunk already exists, not storing: {:?}",
                 self,
+                utils::LogId(message_id),
                 data.address()
             );
             Ok(())
@@ -128,8 +129,9 @@ impl IDataHolder {
                 }
                 _ => {
                     error!(
-                        "{}: Invalid DeleteUnpub(IData::Pub) encountered: {:?}",
-                        self, message_id
+                        "{}: {}: Invalid DeleteUnpub(IData::Pub) encountered",
+                        self,
+                        utils::LogId(message_id)
                     );
                     Err(NdError::InvalidOperation)
                 }
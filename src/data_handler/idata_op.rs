@@ -6,11 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{action::Action, rpc::Rpc};
+use crate::{action::Action, rpc::Rpc, utils};
 use log::warn;
 use safe_nd::{
-    Error as NdError, IData, IDataAddress, MessageId, PublicId, Request, Response,
-    Result as NdResult, XorName,
+    Error as NdError, IData, IDataAddress, MessageId, PubImmutableData, PublicId, Response,
+    Result as NdResult, UnpubImmutableData, XorName,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
@@ -33,24 +33,14 @@ pub(crate) enum RpcState {
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 #[allow(clippy::enum_variant_names)]
 pub(crate) enum IDataRequest {
-    /// Put ImmutableData.
-    PutIData(IData),
+    /// Put ImmutableData.  Only the address is kept, as the data itself is sent to the holders.
+    PutIData(IDataAddress),
     /// Get ImmutableData.
     GetIData(IDataAddress),
     /// Delete unpublished ImmutableData.
     DeleteUnpubIData(IDataAddress),
 }
 
-impl Into<Request> for &IDataRequest {
-    fn into(self) -> Request {
-        match self {
-            IDataRequest::PutIData(ref data) => Request::PutIData(data.clone()),
-            IDataRequest::GetIData(ref address) => Request::GetIData(*address),
-            IDataRequest::DeleteUnpubIData(ref address) => Request::DeleteUnpubIData(*address),
-        }
-    }
-}
-
 /// The type of ImmutableData operation.
 #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub(crate) enum OpType {
@@ -65,6 +55,8 @@ pub(crate) struct IDataOp {
     client: PublicId,
     request: IDataRequest,
     pub rpc_states: BTreeMap<XorName, RpcState>,
+    // Whether the client handlers have been sent the response to a Get.
+    responded: bool,
 }
 
 impl IDataOp {
@@ -76,6 +68,7 @@ impl IDataOp {
                 .into_iter()
                 .map(|holder| (holder, RpcState::Sent))
                 .collect(),
+            responded: false,
         }
     }
 
@@ -83,17 +76,6 @@ impl IDataOp {
         &self.client
     }
 
-    pub fn request(&self) -> Request {
-        (&self.request).into()
-    }
-
-    pub fn is_any_actioned(&self) -> bool {
-        self.rpc_states.values().any(|rpc_state| match rpc_state {
-            RpcState::Actioned(_) => true,
-            _ => false,
-        })
-    }
-
     pub fn op_type(&self) -> OpType {
         match self.request {
             IDataRequest::PutIData(_) => OpType::Put,
@@ -129,8 +111,9 @@ impl IDataOp {
     ) -> Option<IDataAddress> {
         if let IDataRequest::GetIData(_) = self.request {
             warn!(
-                "{}: Expected PutIData or DeleteUnpubIData for {:?}, but found GetIData",
-                own_id, message_id
+                "{}: {}: Expected PutIData or DeleteUnpubIData, but found GetIData",
+                own_id,
+                utils::LogId(message_id)
             );
             return None;
         }
@@ -138,8 +121,9 @@ impl IDataOp {
         self.set_to_actioned(&sender, result.err(), own_id)?;
 
         match self.request {
-            IDataRequest::PutIData(ref data) => Some(*data.address()),
-            IDataRequest::DeleteUnpubIData(address) => Some(address),
+            IDataRequest::PutIData(address) | IDataRequest::DeleteUnpubIData(address) => {
+                Some(address)
+            }
             IDataRequest::GetIData(_) => unreachable!(), // we checked above
         }
     }
@@ -151,7 +135,6 @@ impl IDataOp {
         own_id: String,
         message_id: MessageId,
     ) -> Option<Action> {
-        let is_already_actioned = self.is_any_actioned();
         let address = if let IDataRequest::GetIData(address) = self.request {
             address
         } else {
@@ -164,11 +147,31 @@ impl IDataOp {
             return None;
         };
 
+        // Never pass on data which doesn't match the requested address.  The holder's response is
+        // recorded as an error against it, and we wait for the other holders if there are any.
+        let is_corrupt = match result {
+            Ok(ref data) => !is_intact(data, &address),
+            Err(_) => false,
+        };
+        let result = if is_corrupt {
+            warn!(
+                "{}: {}: {} returned corrupt data for {:?}",
+                own_id,
+                utils::LogId(message_id),
+                sender,
+                address
+            );
+            Err(NdError::from("Holder returned corrupt data"))
+        } else {
+            result
+        };
+
         let response = Response::GetIData(result.clone());
         self.set_to_actioned(&sender, result.err(), own_id)?;
-        if is_already_actioned {
+        if self.responded || (is_corrupt && !self.concluded()) {
             None
         } else {
+            self.responded = true;
             Some(Action::RespondToClientHandlers {
                 sender: *address.name(),
                 rpc: Rpc::Response {
@@ -199,3 +202,93 @@ impl IDataOp {
             .map(|rpc_state| *rpc_state = RpcState::Actioned(got_error_response))
     }
 }
+
+// Returns whether the contents of `data` hash to `address`.
+fn is_intact(data: &IData, address: &IDataAddress) -> bool {
+    let computed_address = match data {
+        IData::Pub(data) => *PubImmutableData::new(data.value().clone()).address(),
+        IData::Unpub(data) => {
+            *UnpubImmutableData::new(data.value().clone(), *data.owner()).address()
+        }
+    };
+    computed_address == *address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use safe_nd::NodeFullId;
+    use std::iter;
+    use unwrap::unwrap;
+
+    #[test]
+    fn corrupt_get_response_is_not_passed_on() {
+        let mut rng = rand::thread_rng();
+        let client = PublicId::Node(NodeFullId::new(&mut rng).public_id().clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+        let other_data = IData::Pub(PubImmutableData::new(vec![4, 5, 6]));
+        let holders = (0..2).map(|_| rng.gen()).collect::<Vec<XorName>>();
+        let message_id = MessageId::new();
+        let own_id = "test".to_string();
+
+        let mut idata_op = IDataOp::new(
+            client,
+            IDataRequest::GetIData(*data.address()),
+            holders.iter().cloned().collect(),
+        );
+
+        // The first holder returns the wrong data, so nothing is passed on yet.
+        assert!(idata_op
+            .handle_get_idata_resp(holders[0], Ok(other_data), own_id.clone(), message_id)
+            .is_none());
+        assert_eq!(idata_op.get_any_errors().len(), 1);
+        assert!(idata_op.get_any_errors().contains_key(&holders[0]));
+
+        // The second holder returns the right data, which is passed on.
+        match idata_op.handle_get_idata_resp(holders[1], Ok(data.clone()), own_id, message_id) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::GetIData(result),
+                        ..
+                    },
+                ..
+            }) => assert_eq!(unwrap!(result), data),
+            _ => panic!("Expected the intact data to be returned"),
+        }
+        assert!(idata_op.concluded());
+    }
+
+    #[test]
+    fn corrupt_get_response_from_every_holder_is_an_error() {
+        let mut rng = rand::thread_rng();
+        let client = PublicId::Node(NodeFullId::new(&mut rng).public_id().clone());
+        let data = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+        let other_data = IData::Pub(PubImmutableData::new(vec![4, 5, 6]));
+        let holder: XorName = rng.gen();
+
+        let mut idata_op = IDataOp::new(
+            client,
+            IDataRequest::GetIData(*data.address()),
+            iter::once(holder).collect(),
+        );
+
+        match idata_op.handle_get_idata_resp(
+            holder,
+            Ok(other_data),
+            "test".to_string(),
+            MessageId::new(),
+        ) {
+            Some(Action::RespondToClientHandlers {
+                rpc:
+                    Rpc::Response {
+                        response: Response::GetIData(result),
+                        ..
+                    },
+                ..
+            }) => assert!(result.is_err()),
+            _ => panic!("Expected an error to be returned"),
+        }
+    }
+}
@@ -59,11 +59,17 @@ impl MDataHandler {
         address: &MDataAddress,
         requester: &PublicId,
         action: MDataAction,
+        message_id: MessageId,
     ) -> Option<NdResult<MData>> {
         let requester_pk = if let Some(pk) = utils::own_key(&requester) {
             pk
         } else {
-            error!("Logic error: requester {:?} must not be Node", requester);
+            error!(
+                "{}: {}: Logic error: requester {:?} must not be Node",
+                self,
+                utils::LogId(message_id),
+                requester
+            );
             return None;
         };
 
@@ -239,7 +245,7 @@ impl MDataHandler {
         address: MDataAddress,
         message_id: MessageId,
     ) -> Option<Action> {
-        let result = self.get_mdata_chunk(&address, &requester, MDataAction::Read)?;
+        let result = self.get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?;
 
         Some(Action::RespondToClientHandlers {
             sender: *address.name(),
@@ -260,7 +266,7 @@ impl MDataHandler {
         message_id: MessageId,
     ) -> Option<Action> {
         let result = self
-            .get_mdata_chunk(&address, &requester, MDataAction::Read)?
+            .get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?
             .map(|data| data.shell());
 
         Some(Action::RespondToClientHandlers {
@@ -282,7 +288,7 @@ impl MDataHandler {
         message_id: MessageId,
     ) -> Option<Action> {
         let result = self
-            .get_mdata_chunk(&address, &requester, MDataAction::Read)?
+            .get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?
             .map(|data| data.version());
 
         Some(Action::RespondToClientHandlers {
@@ -304,7 +310,7 @@ impl MDataHandler {
         key: &[u8],
         message_id: MessageId,
     ) -> Option<Action> {
-        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read)?;
+        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?;
 
         let response = Response::GetMDataValue(res.and_then(|data| {
             match data {
@@ -340,7 +346,7 @@ impl MDataHandler {
         message_id: MessageId,
     ) -> Option<Action> {
         let result = self
-            .get_mdata_chunk(&address, &requester, MDataAction::Read)?
+            .get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?
             .map(|data| data.keys());
 
         Some(Action::RespondToClientHandlers {
@@ -361,7 +367,7 @@ impl MDataHandler {
         address: MDataAddress,
         message_id: MessageId,
     ) -> Option<Action> {
-        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read)?;
+        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?;
 
         let response = Response::ListMDataValues(res.and_then(|data| match data {
             MData::Seq(md) => Ok(md.values().into()),
@@ -386,7 +392,7 @@ impl MDataHandler {
         address: MDataAddress,
         message_id: MessageId,
     ) -> Option<Action> {
-        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read)?;
+        let res = self.get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?;
 
         let response = Response::ListMDataEntries(res.and_then(|data| match data {
             MData::Seq(md) => Ok(md.entries().clone().into()),
@@ -412,7 +418,7 @@ impl MDataHandler {
         message_id: MessageId,
     ) -> Option<Action> {
         let result = self
-            .get_mdata_chunk(&address, &requester, MDataAction::Read)?
+            .get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?
             .map(|data| data.permissions());
 
         Some(Action::RespondToClientHandlers {
@@ -435,7 +441,7 @@ impl MDataHandler {
         message_id: MessageId,
     ) -> Option<Action> {
         let result = self
-            .get_mdata_chunk(&address, &requester, MDataAction::Read)?
+            .get_mdata_chunk(&address, &requester, MDataAction::Read, message_id)?
             .and_then(|data| data.user_permissions(user).map(MDataPermissionSet::clone));
 
         Some(Action::RespondToClientHandlers {
@@ -56,7 +56,10 @@ pub use quic_p2p;
 
 pub use crate::{
     chunk_store::error::Error as ChunkStoreError,
-    client_handler::COST_OF_PUT,
+    client_handler::{
+        BalanceEvent, BalanceEventKind, ClientHandlerStats, FlatPricing, FreeTier, ImportPolicy,
+        PricingPolicy, ProportionalPricing, COST_OF_PUT,
+    },
     config_handler::Config,
     error::{Error, Result},
     vault::{Command, Vault},
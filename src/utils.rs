@@ -13,10 +13,16 @@ use log::{error, trace};
 use pickledb::{PickleDb, PickleDbDumpPolicy};
 use rand::{distributions::Standard, thread_rng, Rng};
 use safe_nd::{
-    ClientPublicId, Coins, IDataAddress, PublicId, PublicKey, Request, Result as NdResult, XorName,
+    ClientPublicId, Coins, IDataAddress, MessageId, PublicId, PublicKey, Request,
+    Result as NdResult, XorName,
 };
 use serde::Serialize;
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
 use unwrap::unwrap;
 
 pub(crate) fn new_db<D: AsRef<Path>, N: AsRef<Path>>(
@@ -50,6 +56,11 @@ pub(crate) fn serialise<T: Serialize>(data: &T) -> Vec<u8> {
     unwrap!(bincode::serialize(data))
 }
 
+/// Returns the serialised size of `data`, or an error if its `Serialize` implementation fails.
+pub(crate) fn serialised_size<T: Serialize>(data: &T) -> bincode::Result<u64> {
+    bincode::serialized_size(data)
+}
+
 /// Returns the client's public ID, the owner's public ID, or None depending on whether `public_id`
 /// represents a Client, App or Node respectively.
 pub(crate) fn owner(public_id: &PublicId) -> Option<&ClientPublicId> {
@@ -226,6 +237,8 @@ pub(crate) fn authorisation_kind(request: &Request) -> AuthorisationKind {
     }
 }
 
+/// Returns the refund due if a Put failed.  Client handlers refund a failed mutation exactly what
+/// they charged for it, so for those only whether a refund is given matters.
 pub(crate) fn get_refund_for_put<T>(result: &NdResult<T>) -> Option<Coins> {
     if result.is_err() {
         Some(*COST_OF_PUT)
@@ -233,3 +246,17 @@ pub(crate) fn get_refund_for_put<T>(result: &NdResult<T>) -> Option<Coins> {
         None
     }
 }
+
+/// Formats a `MessageId` for logging.  Every log line about a request puts its ID straight after
+/// the node's own ID, so one request can be followed through all the handlers:
+///
+/// ```text
+/// 1a2b3c..: MessageId(4d5e6f..): Received PutIData(..) from Client(7a8b9c..)
+/// ```
+pub(crate) struct LogId(pub MessageId);
+
+impl Display for LogId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{:?}", self.0)
+    }
+}
@@ -9,7 +9,9 @@
 use crate::{
     action::Action,
     adult::Adult,
-    client_handler::ClientHandler,
+    client_handler::{
+        BalanceEvent, ClientHandler, ClientHandlerStats, ImportPolicy, PricingPolicy,
+    },
     coins_handler::CoinsHandler,
     data_handler::DataHandler,
     quic_p2p::{Event, NodeInfo},
@@ -17,11 +19,12 @@ use crate::{
     utils, Config, Error, Result,
 };
 use bincode;
-use crossbeam_channel::{select, Receiver};
+use crossbeam_channel::{select, Receiver, Sender};
 use log::{error, info, trace};
-use safe_nd::{NodeFullId, Request, XorName};
+use safe_nd::{Coins, IDataAddress, NodeFullId, PublicKey, Request, XorName};
 use std::{
     cell::Cell,
+    collections::BTreeSet,
     fmt::{self, Display, Formatter},
     fs,
     path::PathBuf,
@@ -53,6 +56,8 @@ pub(crate) enum Init {
 pub enum Command {
     /// Shutdown the vault
     Shutdown,
+    /// Enable or disable read-only mode (see `Vault::set_read_only`)
+    SetReadOnly(bool),
 }
 
 /// Main vault struct.
@@ -133,6 +138,71 @@ impl Vault {
         }
     }
 
+    /// Sets the policy used to price Puts of data.  Has no effect if this vault isn't an elder.
+    pub fn set_pricing_policy(&mut self, pricing: Box<dyn PricingPolicy>) {
+        if let Some(client_handler) = self.client_handler_mut() {
+            client_handler.set_pricing_policy(pricing);
+        }
+    }
+
+    /// Sets whether clients' requests to store new data (Puts of any data type, and the creation of
+    /// login packets) are rejected.  Gets and mutations of existing data are still handled, and
+    /// rejected requests aren't charged.  Has no effect if this vault isn't an elder.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        if let Some(client_handler) = self.client_handler_mut() {
+            client_handler.set_read_only(read_only);
+        }
+    }
+
+    /// Sets the channel to which a timestamped event is sent for every change to a balance held by
+    /// this vault, e.g. to keep an audit log.  Handling requests never waits for the receiver: if
+    /// `sender` is bounded and full, events are dropped and counted in `client_handler_stats()`.
+    /// Has no effect if this vault isn't an elder.
+    pub fn set_balance_event_sender(&mut self, sender: Sender<BalanceEvent>) {
+        if let Some(client_handler) = self.client_handler_mut() {
+            client_handler.set_balance_event_sender(sender);
+        }
+    }
+
+    /// Returns an iterator over all the balances held by this vault, with their owners' keys.  It's
+    /// empty if this vault isn't an elder.
+    pub fn balances<'a>(&'a self) -> impl Iterator<Item = (PublicKey, Coins)> + 'a {
+        self.client_handler()
+            .into_iter()
+            .flat_map(ClientHandler::balances)
+    }
+
+    /// Returns a copy of all the balances held by this vault, with their owners' keys.
+    pub fn export_balances(&self) -> Vec<(PublicKey, Coins)> {
+        self.balances().collect()
+    }
+
+    /// Creates the given balances, e.g. to seed a new network.  Balances which already exist are
+    /// handled according to `policy`.  Returns the number of balances created or changed, which is
+    /// always 0 if this vault isn't an elder.
+    pub fn import_balances<I>(&mut self, balances: I, policy: ImportPolicy) -> usize
+    where
+        I: IntoIterator<Item = (PublicKey, Coins)>,
+    {
+        self.client_handler_mut().map_or(0, |client_handler| {
+            client_handler.import_balances(balances, policy)
+        })
+    }
+
+    /// Returns a summary of the state held by this vault's client handler, or `None` if this vault
+    /// isn't an elder.
+    pub fn client_handler_stats(&self) -> Option<ClientHandlerStats> {
+        self.client_handler().map(ClientHandler::stats)
+    }
+
+    /// Returns the nodes holding the ImmutableData chunk at `address`, as recorded by this vault's
+    /// data handler.  The set is empty if the chunk is unknown or this vault isn't an elder.
+    pub fn idata_holders_of(&self, address: &IDataAddress) -> BTreeSet<XorName> {
+        self.data_handler()
+            .map(|data_handler| data_handler.idata_holders_of(address))
+            .unwrap_or_default()
+    }
+
     /// Runs the main event loop. Blocks until the vault is terminated.
     // FIXME: remove when https://github.com/crossbeam-rs/crossbeam/issues/404 is resolved
     #[allow(clippy::zero_ptr, clippy::drop_copy)]
@@ -147,9 +217,13 @@ impl Vault {
                     }
                 }
                 recv(self.command_receiver) -> command => {
-                    if let Ok(Command::Shutdown) = command {
-                        trace!("{}: Shutdown command received", self);
-                        break
+                    match command {
+                        Ok(Command::Shutdown) => {
+                            trace!("{}: Shutdown command received", self);
+                            break
+                        }
+                        Ok(Command::SetReadOnly(read_only)) => self.set_read_only(read_only),
+                        Err(_) => (),
                     }
                 }
             }
@@ -329,8 +403,6 @@ impl Vault {
         true
     }
 
-    // TODO - remove this
-    #[allow(unused)]
     fn client_handler(&self) -> Option<&ClientHandler> {
         match &self.state {
             State::Elder {
@@ -350,8 +422,6 @@ impl Vault {
         }
     }
 
-    // TODO - remove this
-    #[allow(unused)]
     fn data_handler(&self) -> Option<&DataHandler> {
         match &self.state {
             State::Elder {
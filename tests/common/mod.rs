@@ -52,6 +52,12 @@ pub struct Environment {
 
 impl Environment {
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    // Creates an environment whose vault is started with `config`.  The vault's root directory is
+    // always set to a new temporary directory.
+    pub fn with_config(config: Config) -> Self {
         let do_format = move |formatter: &mut Formatter, record: &Record<'_>| {
             let now = formatter.timestamp();
             writeln!(
@@ -76,7 +82,7 @@ impl Environment {
         Self {
             rng,
             network: Network::new(network_rng),
-            vault: TestVault::new(),
+            vault: TestVault::new(config),
         }
     }
 
@@ -84,6 +90,14 @@ impl Environment {
         &mut self.rng
     }
 
+    pub fn vault(&self) -> &Vault {
+        &self.vault
+    }
+
+    pub fn vault_mut(&mut self) -> &mut Vault {
+        &mut self.vault
+    }
+
     // Poll the mock network and the environment's vault.
     pub fn poll(&mut self) {
         let mut progress = true;
@@ -130,10 +144,9 @@ struct TestVault {
 }
 
 impl TestVault {
-    fn new() -> Self {
+    fn new(mut config: Config) -> Self {
         let root_dir = unwrap!(TempDir::new("safe_vault"));
 
-        let mut config = Config::default();
         config.set_root_dir(root_dir.path());
 
         let (_, command_rx) = crossbeam_channel::bounded(0);
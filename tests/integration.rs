@@ -38,8 +38,14 @@ use safe_nd::{
     SeqAppendOnly, SeqMutableData, Transaction, UnpubImmutableData, UnpubSeqAppendOnlyData,
     UnpubUnseqAppendOnlyData, UnseqAppendOnly, UnseqMutableData, XorName,
 };
-use safe_vault::COST_OF_PUT;
-use std::collections::{BTreeMap, BTreeSet};
+use safe_vault::{
+    BalanceEventKind, ClientHandlerStats, Config, FlatPricing, FreeTier, ImportPolicy,
+    PricingPolicy, COST_OF_PUT,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::SystemTime,
+};
 use unwrap::unwrap;
 
 #[test]
@@ -359,6 +365,90 @@ fn coin_operations() {
     common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, amount_b);
 }
 
+#[test]
+fn import_balances() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+    common::create_balance(&mut env, &mut client_a, None, 10);
+
+    let key_a = *client_a.public_id().public_key();
+    let key_b = *client_b.public_id().public_key();
+    let coins = |nano| unwrap!(Coins::from_nano(nano));
+
+    // A's existing balance is left alone when skipping duplicates.
+    let imported = env.vault_mut().import_balances(
+        vec![(key_a, coins(5)), (key_b, coins(3))],
+        ImportPolicy::Skip,
+    );
+    assert_eq!(imported, 1);
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, coins(10));
+    common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, coins(3));
+
+    // Merging adds to the existing balances.
+    let imported = env.vault_mut().import_balances(
+        vec![(key_a, coins(5)), (key_b, coins(3))],
+        ImportPolicy::Merge,
+    );
+    assert_eq!(imported, 2);
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, coins(15));
+    common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, coins(6));
+}
+
+#[test]
+fn export_balances() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+    common::create_balance(&mut env, &mut client_a, None, 10);
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), 4);
+
+    let key_a = *client_a.public_id().public_key();
+    let key_b = *client_b.public_id().public_key();
+    let mut exported = env.vault().export_balances();
+    exported.sort_by_key(|(_, coins)| coins.as_nano());
+    let expected = vec![
+        (key_b, unwrap!(Coins::from_nano(4))),
+        (key_a, unwrap!(Coins::from_nano(5))),
+    ];
+    assert_eq!(exported, expected);
+
+    // The export is unaffected by later changes.
+    common::transfer_coins(&mut env, &mut client_a, &mut client_b, 2, 1);
+    assert_eq!(exported, expected);
+    assert_eq!(env.vault().balances().count(), 2);
+}
+
+#[test]
+fn create_balance_with_insufficient_funds() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let client_b = env.new_connected_client();
+
+    let balance_a = unwrap!(Coins::from_nano(10));
+    common::create_balance(&mut env, &mut client_a, None, balance_a);
+
+    // A can pay for creating the balance, but can't fund it, so nothing is charged.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_a,
+        Request::CreateBalance {
+            new_balance_owner: *client_b.public_id().public_key(),
+            amount: unwrap!(Coins::from_nano(20)),
+            transaction_id: 1,
+        },
+        NdError::InsufficientBalance,
+    );
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, balance_a);
+    assert_eq!(
+        unwrap!(env.vault().client_handler_stats()).pending_requests,
+        0
+    );
+}
+
 #[test]
 fn create_balance_that_already_exists() {
     let mut env = Environment::new();
@@ -401,6 +491,36 @@ fn create_balance_that_already_exists() {
     common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, balance_a);
 }
 
+#[test]
+fn client_handler_stats() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    let stats = unwrap!(env.vault().client_handler_stats());
+    assert_eq!(stats.balance_count, 0);
+    assert_eq!(stats.total_balance_nano, 0);
+
+    common::create_balance(&mut env, &mut client_a, None, 10);
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), 5);
+
+    // B's balance was funded from A's, and A paid for creating it.
+    let stats = unwrap!(env.vault().client_handler_stats());
+    assert_eq!(stats.balance_count, 2);
+    assert_eq!(stats.total_balance_nano, 10 - COST_OF_PUT.as_nano());
+    assert_eq!(stats.pending_requests, 0);
+    assert_eq!(stats.dropped_balance_events, 0);
+
+    // Stats can be exported as JSON for admin tooling.
+    let json = unwrap!(serde_json::to_string(&stats));
+    assert!(json.contains("\"balance_count\":2"));
+    assert_eq!(
+        unwrap!(serde_json::from_str::<ClientHandlerStats>(&json)),
+        stats
+    );
+}
+
 #[test]
 fn transfer_coins_to_balance_that_doesnt_exist() {
     let mut env = Environment::new();
@@ -434,6 +554,35 @@ fn transfer_coins_to_balance_that_doesnt_exist() {
     client_b.expect_no_new_message();
 }
 
+#[test]
+fn transfer_coins_that_would_overflow_balance() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    // B's balance holds the maximum possible value.
+    let max_nano = (u64::from(u32::max_value()) + 1) * 1_000_000_000;
+    let balance_a = unwrap!(Coins::from_nano(10));
+    let balance_b = unwrap!(Coins::from_nano(max_nano));
+    common::create_balance(&mut env, &mut client_a, None, balance_a);
+    common::create_balance(&mut env, &mut client_b, None, balance_b);
+
+    // Transferring any more to B is rejected rather than wrapping, and A is refunded.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_a,
+        Request::TransferCoins {
+            destination: *client_b.public_id().name(),
+            amount: unwrap!(Coins::from_nano(1)),
+            transaction_id: 1,
+        },
+        NdError::ExcessiveValue,
+    );
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, balance_a);
+    common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, balance_b);
+}
+
 #[test]
 fn coin_operations_by_app() {
     let mut env = Environment::new();
@@ -1966,6 +2115,460 @@ fn put_immutable_data() {
     common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, expected_b);
 }
 
+#[test]
+fn put_pub_immutable_data_stored_by_another_client() {
+    let mut env = Environment::new();
+
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    let start_nano = 1_000_000_000_000;
+    common::create_balance(&mut env, &mut client_a, None, start_nano * 2);
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), start_nano);
+
+    let mut raw_data = vec![0u8; 1024];
+    env.rng().fill(raw_data.as_mut_slice());
+    let pub_idata = IData::Pub(PubImmutableData::new(raw_data));
+
+    common::perform_mutation(
+        &mut env,
+        &mut client_a,
+        Request::PutIData(pub_idata.clone()),
+    );
+
+    // The chunk is only stored once, but client B is still charged for its Put.
+    common::perform_mutation(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(pub_idata.clone()),
+    );
+    let expected_b = unwrap!(Coins::from_nano(start_nano).checked_sub(*COST_OF_PUT));
+    common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, expected_b);
+
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetIData(*pub_idata.address()),
+        pub_idata,
+    );
+}
+
+#[test]
+fn put_immutable_data_at_balance_boundary() {
+    let mut env = Environment::new();
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    // Seed client B with exactly enough to pay for a single Put.
+    let start_nano = 1_000_000_000_000;
+    common::create_balance(&mut env, &mut client_a, None, start_nano);
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), *COST_OF_PUT);
+
+    let owner = *client_b.public_id().public_key();
+    let unpub_idata = IData::Unpub(UnpubImmutableData::new(vec![1, 2, 3], owner));
+    common::perform_mutation(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(unpub_idata.clone()),
+    );
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(0)),
+    );
+
+    // With an empty balance, any further Put is rejected without being attempted.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(unpub_idata.clone()),
+        NdError::InsufficientBalance,
+    );
+    let pub_idata = IData::Pub(PubImmutableData::new(vec![4, 5, 6]));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(pub_idata),
+        NdError::InsufficientBalance,
+    );
+
+    // Deleting doesn't require payment, and the balance is unaffected.
+    common::perform_mutation(
+        &mut env,
+        &mut client_b,
+        Request::DeleteUnpubIData(*unpub_idata.address()),
+    );
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(0)),
+    );
+
+    // Topping up by the cost of a Put allows exactly one more.
+    common::transfer_coins(&mut env, &mut client_a, &mut client_b, *COST_OF_PUT, 1);
+    common::perform_mutation(&mut env, &mut client_b, Request::PutIData(unpub_idata));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(0)),
+    );
+}
+
+#[test]
+fn put_large_immutable_data() {
+    let mut env = Environment::new();
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    let start_nano = 1_000_000_000_000;
+    common::create_balance(&mut env, &mut client_a, None, start_nano);
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), *COST_OF_PUT);
+
+    // A 1 MiB chunk exceeds one unit once serialised, so costs twice the price of a small one.
+    let mut raw_data = vec![0u8; 1024 * 1024];
+    env.rng().fill(raw_data.as_mut_slice());
+    let owner = *client_b.public_id().public_key();
+    let idata = IData::Unpub(UnpubImmutableData::new(raw_data, owner));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(idata.clone()),
+        NdError::InsufficientBalance,
+    );
+    common::send_request_expect_ok(&mut env, &mut client_b, Request::GetBalance, *COST_OF_PUT);
+
+    common::transfer_coins(&mut env, &mut client_a, &mut client_b, *COST_OF_PUT, 1);
+    common::perform_mutation(&mut env, &mut client_b, Request::PutIData(idata.clone()));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(0)),
+    );
+
+    // A failed Put is refunded in full.
+    common::transfer_coins(
+        &mut env,
+        &mut client_a,
+        &mut client_b,
+        common::multiply_coins(*COST_OF_PUT, 2),
+        2,
+    );
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_b,
+        Request::PutIData(idata),
+        NdError::DataExists,
+    );
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_b,
+        Request::GetBalance,
+        common::multiply_coins(*COST_OF_PUT, 2),
+    );
+}
+
+#[test]
+fn put_immutable_data_with_custom_pricing() {
+    struct FixedPricing;
+
+    impl PricingPolicy for FixedPricing {
+        fn cost(&self, _data_size: u64) -> Coins {
+            unwrap!(Coins::from_nano(3))
+        }
+    }
+
+    let mut env = Environment::new();
+    env.vault_mut().set_pricing_policy(Box::new(FixedPricing));
+    let mut client = env.new_connected_client();
+    common::create_balance(&mut env, &mut client, None, 10);
+
+    let owner = *client.public_id().public_key();
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![1, 2, 3], owner));
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(idata.clone()));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(7)),
+    );
+
+    // A failed Put is refunded what the policy charged.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(idata),
+        NdError::DataExists,
+    );
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(7)),
+    );
+}
+
+#[test]
+fn put_immutable_data_with_free_tier() {
+    let mut env = Environment::new();
+    env.vault_mut()
+        .set_pricing_policy(Box::new(FreeTier::new(100, FlatPricing)));
+    let mut client_a = env.new_connected_client();
+    let mut client_b = env.new_connected_client();
+
+    // Free Puts still need a balance.
+    let small_idata = IData::Pub(PubImmutableData::new(vec![1, 2, 3]));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_a,
+        Request::PutIData(small_idata.clone()),
+        NdError::NoSuchBalance,
+    );
+
+    let start_nano = 10;
+    let balance = unwrap!(Coins::from_nano(start_nano));
+    common::create_balance(&mut env, &mut client_a, None, balance);
+
+    // Data below the threshold is free, including when its Put fails.
+    common::perform_mutation(&mut env, &mut client_a, Request::PutIData(small_idata));
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, balance);
+
+    let owner = *client_a.public_id().public_key();
+    let small_unpub_idata = IData::Unpub(UnpubImmutableData::new(vec![1, 2, 3], owner));
+    common::perform_mutation(
+        &mut env,
+        &mut client_a,
+        Request::PutIData(small_unpub_idata.clone()),
+    );
+    common::send_request_expect_err(
+        &mut env,
+        &mut client_a,
+        Request::PutIData(small_unpub_idata),
+        NdError::DataExists,
+    );
+    common::send_request_expect_ok(&mut env, &mut client_a, Request::GetBalance, balance);
+
+    // Data at or above it is charged.
+    let large_idata = IData::Pub(PubImmutableData::new(vec![1; 100]));
+    common::perform_mutation(&mut env, &mut client_a, Request::PutIData(large_idata));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client_a,
+        Request::GetBalance,
+        unwrap!(balance.checked_sub(*COST_OF_PUT)),
+    );
+
+    // A client with an empty balance can still make free Puts.
+    common::create_balance(&mut env, &mut client_a, Some(&mut client_b), 0);
+    let small_idata = IData::Pub(PubImmutableData::new(vec![4, 5, 6]));
+    common::perform_mutation(&mut env, &mut client_b, Request::PutIData(small_idata));
+}
+
+#[test]
+fn put_data_exceeding_max_object_size() {
+    let owner_id = ClientFullId::new_ed25519(&mut rand::thread_rng());
+    let owner = *owner_id.public_id().public_key();
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![1; 100], owner));
+    let max_object_size = unwrap!(bincode::serialized_size(&idata));
+
+    let mut config = Config::default();
+    config.set_max_object_size(max_object_size);
+    let mut env = Environment::with_config(config);
+    let mut client = env.new_connected_client();
+    let balance = unwrap!(Coins::from_nano(10));
+    common::create_balance(&mut env, &mut client, None, balance);
+
+    // Data just over the limit is rejected without charge.
+    let owner = *client.public_id().public_key();
+    let large_idata = IData::Unpub(UnpubImmutableData::new(vec![1; 101], owner));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(large_idata),
+        NdError::ExceededSize,
+    );
+    common::send_request_expect_ok(&mut env, &mut client, Request::GetBalance, balance);
+
+    // Data at the limit is accepted.
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![1; 100], owner));
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(idata));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(balance.checked_sub(*COST_OF_PUT)),
+    );
+}
+
+#[test]
+fn paid_requests_are_rate_limited() {
+    let mut config = Config::default();
+    config.set_max_paid_requests(2);
+    config.set_paid_request_interval_msec(60_000);
+    let mut env = Environment::with_config(config);
+    let mut client = env.new_connected_client();
+    let balance = unwrap!(Coins::from_nano(10));
+    common::create_balance(&mut env, &mut client, None, balance);
+
+    let owner = *client.public_id().public_key();
+    for value in 0..2 {
+        let idata = IData::Unpub(UnpubImmutableData::new(vec![value], owner));
+        common::perform_mutation(&mut env, &mut client, Request::PutIData(idata));
+    }
+
+    // The third paid request within the interval is rejected without charge.
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![2], owner));
+    let message_id = client.send_request(Request::PutIData(idata));
+    env.poll();
+    match client.expect_response(message_id) {
+        Response::Mutation(Err(error)) => assert!(error.to_string().contains("Rate limited")),
+        x => unexpected!(x),
+    }
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(8)),
+    );
+}
+
+#[test]
+fn balance_events() {
+    let mut env = Environment::new();
+    let mut client = env.new_connected_client();
+    common::create_balance(&mut env, &mut client, None, 10);
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    env.vault_mut().set_balance_event_sender(sender);
+
+    let start = SystemTime::now();
+    let owner = *client.public_id().public_key();
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![1; 100], owner));
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(idata.clone()));
+    // The second Put fails, so is refunded.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(idata),
+        NdError::DataExists,
+    );
+    let end = SystemTime::now();
+
+    let events = receiver.try_iter().collect::<Vec<_>>();
+    let changes = events
+        .iter()
+        .map(|event| (event.owner, event.kind, event.amount))
+        .collect::<Vec<_>>();
+    let charged = (owner, BalanceEventKind::Withdrawn, *COST_OF_PUT);
+    let refunded = (owner, BalanceEventKind::Deposited, *COST_OF_PUT);
+    assert_eq!(changes, vec![charged, charged, refunded]);
+
+    // Events are timestamped in the order the changes were made.
+    let times = events.iter().map(|event| event.time).collect::<Vec<_>>();
+    assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(times.iter().all(|time| start <= *time && *time <= end));
+}
+
+#[test]
+fn put_immutable_data_in_read_only_mode() {
+    let mut env = Environment::new();
+    let mut client = env.new_connected_client();
+    let balance = unwrap!(Coins::from_nano(10));
+    common::create_balance(&mut env, &mut client, None, balance);
+
+    let idata = IData::Pub(PubImmutableData::new(vec![1; 100]));
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(idata.clone()));
+    let balance = unwrap!(balance.checked_sub(*COST_OF_PUT));
+
+    // New data is rejected without charge, but existing data can still be retrieved.
+    env.vault_mut().set_read_only(true);
+    let new_idata = IData::Pub(PubImmutableData::new(vec![2; 100]));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(new_idata.clone()),
+        NdError::from("Vault is read-only"),
+    );
+    common::send_request_expect_ok(&mut env, &mut client, Request::GetBalance, balance);
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetIData(*idata.address()),
+        idata,
+    );
+
+    env.vault_mut().set_read_only(false);
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(new_idata));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(balance.checked_sub(*COST_OF_PUT)),
+    );
+}
+
+#[test]
+fn put_immutable_data_failures_are_distinct() {
+    let mut config = Config::default();
+    config.set_max_object_size(1024);
+    let mut env = Environment::with_config(config);
+    let mut client = env.new_connected_client();
+
+    let owner = *client.public_id().public_key();
+    let idata = IData::Unpub(UnpubImmutableData::new(vec![1; 100], owner));
+    let large_idata = IData::Unpub(UnpubImmutableData::new(vec![1; 2048], owner));
+
+    // No balance.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(idata.clone()),
+        NdError::NoSuchBalance,
+    );
+
+    // A balance too low to pay.
+    common::create_balance(&mut env, &mut client, None, 0);
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(idata),
+        NdError::InsufficientBalance,
+    );
+
+    // Data too large, whatever the balance.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutIData(large_idata),
+        NdError::ExceededSize,
+    );
+}
+
+#[test]
+fn immutable_data_holders() {
+    let mut env = Environment::new();
+    let mut client = env.new_connected_client();
+    common::create_balance(&mut env, &mut client, None, 1_000_000_000_000);
+
+    let owner = *client.public_id().public_key();
+    let unpub_idata = IData::Unpub(UnpubImmutableData::new(vec![1, 2, 3], owner));
+    let address = *unpub_idata.address();
+    assert!(env.vault().idata_holders_of(&address).is_empty());
+
+    common::perform_mutation(&mut env, &mut client, Request::PutIData(unpub_idata));
+    assert_eq!(env.vault().idata_holders_of(&address).len(), 1);
+
+    // The same name under the published address is a different chunk.
+    let pub_address = IDataAddress::Pub(*address.name());
+    assert!(env.vault().idata_holders_of(&pub_address).is_empty());
+
+    common::perform_mutation(&mut env, &mut client, Request::DeleteUnpubIData(address));
+    assert!(env.vault().idata_holders_of(&address).is_empty());
+}
+
 #[test]
 fn get_immutable_data_that_doesnt_exist() {
     let mut env = Environment::new();
@@ -2549,6 +3152,66 @@ fn put_seq_mutable_data() {
     );
 }
 
+#[test]
+fn put_mutable_data_that_already_exists() {
+    let mut env = Environment::new();
+    let mut client = env.new_connected_client();
+
+    common::create_balance(
+        &mut env,
+        &mut client,
+        None,
+        common::multiply_coins(*COST_OF_PUT, 2),
+    );
+
+    let name: XorName = env.rng().gen();
+    let tag = 100;
+    let mdata = MData::Seq(SeqMutableData::new(
+        name,
+        tag,
+        *client.public_id().public_key(),
+    ));
+    common::perform_mutation(&mut env, &mut client, Request::PutMData(mdata.clone()));
+    common::send_request_expect_ok(&mut env, &mut client, Request::GetBalance, *COST_OF_PUT);
+
+    // The failed Put is refunded exactly what it was charged.
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutMData(mdata),
+        NdError::DataExists,
+    );
+    common::send_request_expect_ok(&mut env, &mut client, Request::GetBalance, *COST_OF_PUT);
+}
+
+#[test]
+fn put_mutable_data_with_reserved_type_tag() {
+    let mut config = Config::default();
+    config.set_min_type_tag(100);
+    let mut env = Environment::with_config(config);
+    let mut client = env.new_connected_client();
+    common::create_balance(&mut env, &mut client, None, *COST_OF_PUT);
+
+    let owner = *client.public_id().public_key();
+    let mdata = MData::Seq(SeqMutableData::new(env.rng().gen(), 99, owner));
+    common::send_request_expect_err(
+        &mut env,
+        &mut client,
+        Request::PutMData(mdata),
+        NdError::from("Forbidden type tag"),
+    );
+    common::send_request_expect_ok(&mut env, &mut client, Request::GetBalance, *COST_OF_PUT);
+
+    let mdata = MData::Seq(SeqMutableData::new(env.rng().gen(), 100, owner));
+    common::perform_mutation(&mut env, &mut client, Request::PutMData(mdata));
+    common::send_request_expect_ok(
+        &mut env,
+        &mut client,
+        Request::GetBalance,
+        unwrap!(Coins::from_nano(0)),
+    );
+}
+
 #[test]
 fn put_unseq_mutable_data() {
     let mut env = Environment::new();